@@ -0,0 +1,19 @@
+//Measures raw instruction dispatch by running whole frames of the tiny rom.
+//
+//  cargo bench --bench cpu
+extern crate criterion;
+extern crate rust_gb_emu;
+
+use criterion::{criterion_group,criterion_main,Criterion};
+use rust_gb_emu::gb::gameboy::GameBoy;
+use rust_gb_emu::gb::interconnect::RamInit;
+use rust_gb_emu::gb::testrom;
+
+fn step_frame(c: &mut Criterion){
+	let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+	gb.set_verbose(false);
+	c.bench_function("step_frame",|b| b.iter(|| gb.step_frame()));
+}
+
+criterion_group!(benches,step_frame);
+criterion_main!(benches);
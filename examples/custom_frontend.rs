@@ -0,0 +1,47 @@
+//A frontend of its own, counting frames and forwarding events instead of
+//drawing anything.
+//
+//  cargo run --example custom_frontend
+extern crate rust_gb_emu;
+
+use rust_gb_emu::gb::frontend::Frontend;
+use rust_gb_emu::gb::gameboy::{GameBoy,EmuEvent};
+use rust_gb_emu::gb::interconnect::RamInit;
+use rust_gb_emu::gb::joypad::JoypadState;
+use rust_gb_emu::gb::testrom;
+
+struct FrameCounter{
+	frames: u64,
+	limit: u64,
+}
+
+impl Frontend for FrameCounter{
+	fn present_frame(&mut self,_gb: &GameBoy){
+		self.frames+=1;
+	}
+
+	//hold start to get past title screens.
+	fn poll_input(&mut self)->JoypadState{
+		JoypadState::START
+	}
+
+	fn push_audio(&mut self,_samples: &[i16]){}
+
+	fn should_quit(&self)->bool{
+		self.frames>=self.limit
+	}
+
+	fn handle_event(&mut self,event: EmuEvent){
+		match event{
+			EmuEvent::Rumble(intensity) => println!("rumble at {}",intensity),
+		}
+	}
+}
+
+fn main(){
+	let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+	gb.set_verbose(false);
+	let mut frontend=FrameCounter{frames: 0,limit: 10};
+	gb.run(&mut frontend);
+	println!("ran {} frames",frontend.frames);
+}
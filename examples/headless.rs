@@ -0,0 +1,25 @@
+//Runs a rom (or the built in tiny rom) for a few frames without any
+//output and prints where the cpu ended up.
+//
+//  cargo run --example headless [rom]
+extern crate rust_gb_emu;
+
+use rust_gb_emu::gb::gameboy::GameBoy;
+use rust_gb_emu::gb::interconnect::RamInit;
+use rust_gb_emu::gb::testrom;
+use std::env;
+use std::fs;
+
+fn main(){
+	let rom=match env::args().nth(1){
+		Some(path) => fs::read(&path).unwrap_or_else(|e| panic!("Could not read {}: {}",path,e)),
+		None       => testrom::tiny_rom(),
+	};
+	let mut gb=GameBoy::new(rom,RamInit::Zero);
+	gb.set_verbose(false);
+	for _ in 0..60 {
+		gb.step_frame();
+	}
+	println!("{} frames, {} cycles",gb.frame(),gb.cycles());
+	println!("{}",gb.cpu().state());
+}
@@ -0,0 +1,132 @@
+//The subcommands as data. The usage text and `help <command>` are both
+//generated from this table, so they can't disagree.
+
+pub struct Command{
+	pub name: &'static str,
+	//arguments, after the name.
+	pub args: &'static str,
+	//one line for the command list.
+	pub summary: &'static str,
+	pub about: &'static str,
+	pub examples: &'static [&'static str],
+	pub related: &'static [&'static str],
+}
+
+pub const COMMANDS: [Command;9] = [
+	Command{
+		name: "export-save",
+		args: "<rom> <out.sav>",
+		summary: "copy a rom's save out for another emulator",
+		about: "Writes the battery save of <rom> to <out.sav>, resized to the
+cartridge's ram size.",
+		examples: &["rust-gb-emu export-save zelda.gb ~/saves/zelda.sav"],
+		related: &["import-save","info"],
+	},
+	Command{
+		name: "import-save",
+		args: "<rom> <in.sav>",
+		summary: "replace a rom's save with one from another emulator",
+		about: "Replaces the battery save of <rom> with <in.sav>. Saves of the
+wrong size (RTC footers, padding) are resized to the cartridge's ram
+size with a warning.",
+		examples: &["rust-gb-emu import-save pokered.gb ~/Downloads/red.sav"],
+		related: &["export-save","info"],
+	},
+	Command{
+		name: "compat-run",
+		args: "<dir> [--frames <n>] [--report <file.md>]",
+		summary: "boot every rom in a directory and report how far each got",
+		about: "Boots every .gb/.gbc rom in <dir> for n frames (default 60) and
+writes a Markdown report of which ones ran, panicked or hit
+unimplemented hardware, to stdout or the --report file.",
+		examples: &["rust-gb-emu compat-run roms/","rust-gb-emu compat-run roms/ --frames 600 --report compat.md"],
+		related: &["selftest"],
+	},
+	Command{
+		name: "lockstep",
+		args: "<rom> [--frames <n>] [--ram-init <policy>]",
+		summary: "check debugging aids don't change what the game does",
+		about: "Runs the rom for n frames (default 600) on a plain instance and on
+one with every passive debugging aid enabled, and reports the first
+frame where their states differ.",
+		examples: &["rust-gb-emu lockstep tetris.gb","rust-gb-emu lockstep tetris.gb --frames 3600 --ram-init random:42"],
+		related: &["selftest"],
+	},
+	Command{
+		name: "selftest",
+		args: "",
+		summary: "run the emulator's built in checks",
+		about: "Runs built in checks of the emulator itself and exits with an error
+if any of them fails.",
+		examples: &["rust-gb-emu selftest"],
+		related: &["compat-run","lockstep"],
+	},
+	Command{
+		name: "info",
+		args: "<rom>",
+		summary: "show a rom's header",
+		about: "Prints what the cartridge header says, whether its checksums match
+the rom and anything --strict-header would refuse it for.",
+		examples: &["rust-gb-emu info game.gb"],
+		related: &["export-save","logo-check"],
+	},
+	Command{
+		name: "logo-check",
+		args: "<rom>",
+		summary: "check a rom's Nintendo logo will pass the boot rom",
+		about: "Draws the logo bitmap in the header (0x104-0x133) and compares it
+with the one the boot rom checks. On a mismatch prints the expected
+logo and the bytes that differ, and exits with an error, since a real
+DMG won't boot the rom.",
+		examples: &["rust-gb-emu logo-check homebrew.gb"],
+		related: &["info"],
+	},
+	Command{
+		name: "isa",
+		args: "dump [--json]",
+		summary: "print the instruction set table",
+		about: "Prints every instruction, 0xCB prefixed ones included, with its
+length, cycles (and cycles when a condition holds) and flag effects
+(Z N H C: set from the result, 0, 1 or - for untouched). --json
+prints the same table as a JSON array for other tools.",
+		examples: &["rust-gb-emu isa dump","rust-gb-emu isa dump --json > sm83.json"],
+		related: &["selftest"],
+	},
+	Command{
+		name: "help",
+		args: "[command]",
+		summary: "show this, or more about a command",
+		about: "Without arguments prints the usage. With a command prints its
+arguments, what it does, examples and related commands.",
+		examples: &["rust-gb-emu help lockstep"],
+		related: &[],
+	},
+];
+
+pub fn find(name: &str)->Option<&'static Command>{
+	COMMANDS.iter().find(|c| c.name==name)
+}
+
+//"rust-gb-emu <name> <args>"
+pub fn synopsis(command: &Command)->String{
+	format!("rust-gb-emu {} {}",command.name,command.args).trim_end().to_string()
+}
+
+//one line per command, names lined up.
+pub fn summaries()->String{
+	COMMANDS.iter()
+		.map(|c| format!("  {:<13}{}",c.name,c.summary))
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+pub fn help(command: &Command)->String{
+	let mut text=format!("Usage: {}\n\n{}\n\nExamples:\n",synopsis(command),command.about);
+	for example in command.examples{
+		text+=&format!("  {}\n",example);
+	}
+	if !command.related.is_empty() {
+		text+=&format!("\nSee also: {}\n",command.related.join(", "));
+	}
+	text
+}
@@ -0,0 +1,97 @@
+//Boots every rom in a directory headlessly and reports how far each one
+//got, so the emulator doubles as its own compatibility test.
+use rust_gb_emu::gb::cartridge::Cartridge;
+use rust_gb_emu::gb::gameboy::GameBoy;
+use rust_gb_emu::gb::interconnect::{RamInit,UnimplementedIo};
+use std::any::Any;
+use std::fs;
+use std::panic::{self,AssertUnwindSafe};
+use std::path::Path;
+
+enum Status{
+	//ran for all the requested frames.
+	Ok,
+	//hit an unimplemented!() (IO register, MBC...).
+	Unimplemented(String),
+	Panicked(String),
+}
+
+struct Report{
+	rom: String,
+	title: String,
+	status: Status,
+	frames: u64,
+}
+
+pub fn run(dir: &Path,frames: u64)->Vec<String>{
+	let mut roms: Vec<_>=fs::read_dir(dir)
+		.unwrap_or_else(|e| panic!("Cannot read {}: {}",dir.display(),e))
+		.filter_map(|entry| entry.ok().map(|e| e.path()))
+		.filter(|path| path.extension().is_some_and(|e| e=="gb" || e=="gbc"))
+		.collect();
+	roms.sort();
+
+	//keep panics from cluttering the report
+	let hook=panic::take_hook();
+	panic::set_hook(Box::new(|_| {}));
+	let reports: Vec<Report>=roms.iter().map(|rom| run_rom(rom,frames)).collect();
+	panic::set_hook(hook);
+
+	let mut lines=vec![
+		String::from("| Rom | Title | Result | Frames | Details |"),
+		String::from("|-----|-------|--------|--------|---------|"),
+	];
+	for report in &reports{
+		let (result,details)=match report.status{
+			Status::Ok                   => ("ok",""),
+			Status::Unimplemented(ref m) => ("unimplemented",&m[..]),
+			Status::Panicked(ref m)      => ("panicked",&m[..]),
+		};
+		lines.push(format!("| {} | {} | {} | {} | {} |",
+			report.rom,report.title,result,report.frames,details.replace('|',"\\|")));
+	}
+	let ok=reports.iter().filter(|r| matches!(r.status,Status::Ok)).count();
+	lines.push(String::new());
+	lines.push(format!("{} of {} roms ran for {} frames.",ok,reports.len(),frames));
+	lines
+}
+
+fn run_rom(path: &Path,frames: u64)->Report{
+	let name=path.file_name().map_or(String::new(),|n| n.to_string_lossy().into_owned());
+	let rom=match fs::read(path){
+		Ok(rom) => rom,
+		Err(e)  => return Report{rom: name,title: String::new(),status: Status::Panicked(e.to_string()),frames: 0},
+	};
+	let title=panic::catch_unwind(|| Cartridge::new(rom.clone()).title()).unwrap_or_default();
+	let mut reached=0;
+	let result=panic::catch_unwind(AssertUnwindSafe(|| {
+		let mut gb=GameBoy::new(rom,RamInit::Zero);
+		gb.set_verbose(false);
+		//stop at the first missing register so it shows in the report
+		gb.interconnect_mut().set_unimplemented_io(UnimplementedIo::Panic);
+		while gb.frame()<frames {
+			gb.step();
+			reached=gb.frame();
+		}
+	}));
+	let status=match result{
+		Ok(())       => Status::Ok,
+		Err(payload) => {
+			let message=panic_message(payload);
+			if message.starts_with("not implemented") {
+				Status::Unimplemented(message)
+			}
+			else {
+				Status::Panicked(message)
+			}
+		},
+	};
+	Report{rom: name,title,status,frames: reached}
+}
+
+fn panic_message(payload: Box<dyn Any+Send>)->String{
+	match payload.downcast::<String>(){
+		Ok(message) => *message,
+		Err(payload) => payload.downcast_ref::<&str>().map_or(String::from("unknown panic"),|m| m.to_string()),
+	}
+}
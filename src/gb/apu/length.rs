@@ -0,0 +1,112 @@
+//Length counters, which silence a channel once it played for as long as
+//NRx1 asked. The frame sequencer clocks them on every other of its 512Hz
+//steps.
+//
+//Enabling the length in NRx4 while the sequencer's next step won't clock
+//lengths (the first half of a length period) clocks the counter once
+//right away, which can disable the channel there and then. Triggering
+//with the counter at 0 reloads it to the maximum, minus that same extra
+//clock when the length is enabled in the first half.
+
+#[derive(Debug,Clone,Copy)]
+pub struct LengthCounter{
+	//64, 256 for the wave channel.
+	max: u16,
+	remaining: u16,
+	//NRx4 bit 6.
+	enabled: bool,
+}
+
+impl LengthCounter{
+	pub fn new(max: u16)->Self{
+		LengthCounter{max,remaining: 0,enabled: false}
+	}
+
+	pub fn remaining(&self)->u16{
+		self.remaining
+	}
+
+	//NRx1, the length bits of it.
+	pub fn load(&mut self,data: u8){
+		self.remaining=self.max-(data as u16&(self.max-1));
+	}
+
+	//NRx4. `extra_clock` when the frame sequencer's next step doesn't clock
+	//lengths. Returns false when the channel has to be disabled.
+	pub fn write_control(&mut self,data: u8,extra_clock: bool)->bool{
+		let trigger=data&0x80!=0;
+		let was_enabled=self.enabled;
+		self.enabled=data&0x40!=0;
+		let mut alive=true;
+		if extra_clock && !was_enabled && self.enabled && self.remaining>0 {
+			self.remaining-=1;
+			alive=self.remaining>0 || trigger;
+		}
+		if trigger && self.remaining==0 {
+			self.remaining=self.max;
+			if extra_clock && self.enabled {
+				self.remaining-=1;
+			}
+		}
+		alive
+	}
+
+	//a frame sequencer length step. Returns false when it ran out.
+	pub fn clock(&mut self)->bool{
+		if self.enabled && self.remaining>0 {
+			self.remaining-=1;
+			return self.remaining>0;
+		}
+		true
+	}
+
+	//power off clears NRx4 but, on a DMG, keeps the counter itself.
+	pub fn power_off(&mut self){
+		self.enabled=false;
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::LengthCounter;
+
+	#[test]
+	fn enabling_in_the_first_half_clocks_once(){
+		let mut length=LengthCounter::new(64);
+		length.load(54);
+		assert!(length.write_control(0x40,false));
+		assert_eq!(length.remaining(),10);
+		length.write_control(0x00,true);
+		assert!(length.write_control(0x40,true));
+		assert_eq!(length.remaining(),9);
+		//already enabled, no extra clock
+		assert!(length.write_control(0x40,true));
+		assert_eq!(length.remaining(),9);
+	}
+
+	#[test]
+	fn extra_clock_can_disable_the_channel(){
+		let mut length=LengthCounter::new(64);
+		length.load(63);
+		assert!(!length.write_control(0x40,true));
+		assert_eq!(length.remaining(),0);
+		//with a trigger it reloads instead
+		let mut length=LengthCounter::new(64);
+		length.load(63);
+		assert!(length.write_control(0xC0,true));
+		assert_eq!(length.remaining(),63);
+	}
+
+	#[test]
+	fn trigger_reloads_an_expired_counter(){
+		let mut length=LengthCounter::new(256);
+		length.write_control(0x80,true);
+		assert_eq!(length.remaining(),256);
+		let mut length=LengthCounter::new(256);
+		length.write_control(0xC0,false);
+		assert_eq!(length.remaining(),256);
+		assert!(length.clock());
+		assert_eq!(length.remaining(),255);
+	}
+}
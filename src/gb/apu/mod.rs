@@ -0,0 +1,223 @@
+//Sound hardware, 0xFF10-0xFF3F. Nothing is mixed into samples yet: the
+//channels only keep the state games and test roms can see through the
+//registers, which some music engines depend on.
+
+pub mod length;
+pub mod square;
+pub mod wave;
+pub mod noise;
+
+use gb::peripheral::Peripheral;
+use gb::io_registers;
+use self::square::Square;
+use self::wave::Wave;
+use self::noise::Noise;
+
+const START: u16 = 0xFF10;
+const END: u16 = 0xFF3F;
+const NR52: u16 = 0xFF26;
+const POWER: u8 = 0x80;
+//the frame sequencer steps at 512Hz.
+const SEQUENCER_PERIOD: u32 = 8192;
+
+#[derive(Debug)]
+pub struct Apu{
+	//NR10-NR52 as last written.
+	registers: [u8;0x17],
+	square1: Square,
+	square2: Square,
+	wave: Wave,
+	noise: Noise,
+	//cycles into the current frame sequencer step.
+	sequencer_cycles: u32,
+	//next step to run, 0-7. Even ones clock the lengths.
+	sequencer_step: u8,
+}
+
+impl Default for Apu{
+	fn default()->Self{
+		Apu::new()
+	}
+}
+
+impl Apu{
+	//powered on, as the boot rom leaves it.
+	pub fn new()->Self{
+		let mut registers=[0;0x17];
+		registers[(NR52-START) as usize]=POWER;
+		Apu{registers,square1: Square::new(0xFF10),square2: Square::new(0xFF15),
+			wave: Wave::default(),noise: Noise::default(),sequencer_cycles: 0,sequencer_step: 0}
+	}
+
+	pub fn square1(&self)->&Square{
+		&self.square1
+	}
+
+	pub fn square2(&self)->&Square{
+		&self.square2
+	}
+
+	pub fn wave(&self)->&Wave{
+		&self.wave
+	}
+
+	pub fn noise(&self)->&Noise{
+		&self.noise
+	}
+
+	fn powered(&self)->bool{
+		self.registers[(NR52-START) as usize]&POWER!=0
+	}
+
+	//NR52 low nibble.
+	fn status(&self)->u8{
+		[self.square1.enabled(),self.square2.enabled(),self.wave.enabled(),self.noise.enabled()]
+			.iter().enumerate()
+			.fold(0,|status,(i,&on)| if on {status|1<<i} else {status})
+	}
+
+	fn clock_lengths(&mut self){
+		self.square1.clock_length();
+		self.square2.clock_length();
+		self.wave.clock_length();
+		self.noise.clock_length();
+	}
+
+	fn power_off(&mut self){
+		//every register is cleared, wave ram and (on a DMG) the length
+		//counters survive
+		self.registers=[0;0x17];
+		self.square1.power_off();
+		self.square2.power_off();
+		self.wave.power_off();
+		self.noise.power_off();
+	}
+}
+
+impl Peripheral for Apu{
+	fn handles(&self,address: u16)->bool{
+		(START..=END).contains(&address)
+	}
+
+	fn step(&mut self,cycles: u32){
+		if !self.powered() {
+			return;
+		}
+		self.sequencer_cycles+=cycles;
+		while self.sequencer_cycles>=SEQUENCER_PERIOD{
+			self.sequencer_cycles-=SEQUENCER_PERIOD;
+			if self.sequencer_step.is_multiple_of(2) {
+				self.clock_lengths();
+			}
+			self.sequencer_step=(self.sequencer_step+1)%8;
+		}
+		self.wave.step(cycles);
+		self.noise.step(cycles);
+	}
+
+	//unused and write only bits read as 1, see gb::io_registers.
+	fn read(&self,address: u16)->u8{
+		let value=match address{
+			wave::RAM_START..=wave::RAM_END => return self.wave.read_ram(address),
+			NR52                            => self.registers[(NR52-START) as usize]&POWER|self.status(),
+			0xFF10..=0xFF25                 => self.registers[(address-START) as usize],
+			_                               => 0,
+		};
+		value|io_registers::read_mask(address)
+	}
+
+	fn write(&mut self,address: u16,data: u8){
+		match address{
+			wave::RAM_START..=wave::RAM_END => self.wave.write_ram(address,data),
+			NR52                            => {
+				if data&POWER==0 {
+					self.power_off();
+				}
+				else if !self.powered() {
+					self.sequencer_cycles=0;
+					self.sequencer_step=0;
+				}
+				self.registers[(NR52-START) as usize]=data&POWER;
+			},
+			0xFF10..=0xFF25 if self.powered() => {
+				self.registers[(address-START) as usize]=data;
+				//the next step won't clock lengths
+				let extra_clock=!self.sequencer_step.is_multiple_of(2);
+				match address{
+					0xFF10..=0xFF14           => self.square1.write_register(address,data,extra_clock),
+					0xFF15..=0xFF19           => self.square2.write_register(address,data,extra_clock),
+					wave::NR30..=wave::NR34   => self.wave.write_register(address,data,extra_clock),
+					noise::NR41..=noise::NR44 => self.noise.write_register(address,data,extra_clock),
+					_                         => {},
+				}
+			},
+			//the rest is read only while powered off, but a DMG still
+			//takes lengths
+			0xFF11 => self.square1.load_length(data),
+			0xFF16 => self.square2.load_length(data),
+			0xFF1B => self.wave.load_length(data),
+			0xFF20 => self.noise.load_length(data),
+			_ => {},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::Apu;
+	use gb::io_registers;
+	use gb::peripheral::Peripheral;
+
+	#[test]
+	fn registers_read_back_through_masks(){
+		let mut apu=Apu::new();
+		for address in 0xFF10..=0xFF25{
+			apu.write(address,0x00);
+		}
+		for address in (0xFF10..=0xFF2F).filter(|&a| a!=0xFF26){
+			assert_eq!(apu.read(address),io_registers::read_mask(address),"{:04X}",address);
+		}
+		assert_eq!(apu.read(0xFF26),0xF0);
+		assert_eq!(apu.read(0xFF1C),0x9F);
+		apu.write(0xFF1C,0x60);
+		assert_eq!(apu.read(0xFF1C),0xFF);
+		//powered off, writes are ignored
+		apu.write(0xFF26,0x00);
+		assert_eq!(apu.read(0xFF26),0x70);
+		apu.write(0xFF24,0x77);
+		assert_eq!(apu.read(0xFF24),0x00);
+	}
+
+	#[test]
+	fn frame_sequencer_runs_lengths_out(){
+		let mut apu=Apu::new();
+		apu.write(0xFF12,0xF0);
+		//one step of length left, enabled and triggered
+		apu.write(0xFF11,0x3F);
+		apu.write(0xFF14,0xC0);
+		assert_eq!(apu.read(0xFF26),0xF1);
+		apu.step(8191);
+		assert_eq!(apu.read(0xFF26),0xF1);
+		apu.step(1);
+		assert_eq!(apu.read(0xFF26),0xF0);
+		//step 1 is next, so enabling the length now clocks it once more
+		apu.write(0xFF11,0x3E);
+		apu.write(0xFF14,0x80);
+		assert_eq!(apu.square1().length().remaining(),2);
+		apu.write(0xFF14,0x40);
+		assert_eq!(apu.square1().length().remaining(),1);
+		assert_eq!(apu.read(0xFF26),0xF1);
+	}
+
+	#[test]
+	fn dmg_lengths_survive_power_off(){
+		let mut apu=Apu::new();
+		apu.write(0xFF26,0x00);
+		apu.write(0xFF20,0x30);
+		apu.write(0xFF23,0xC0);
+		apu.write(0xFF26,0x80);
+		assert_eq!(apu.noise().length().remaining(),16);
+		assert_eq!(apu.read(0xFF23),0xBF);
+	}
+}
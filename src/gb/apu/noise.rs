@@ -0,0 +1,203 @@
+//Channel 4, white noise out of a linear feedback shift register.
+//
+//Every clock XORs the two low bits of the 15 bit LFSR, shifts right and
+//puts the result in bit 14, and in 7 bit ("width") mode in bit 6 too, so
+//the sequence repeats every 127 clocks instead of 32767. The output is
+//bit 0 inverted. The LFSR is clocked every DIVISORS[r] << s cycles, r and
+//s coming from NR43; shifts 14 and 15 don't clock it at all.
+
+use gb::apu::length::LengthCounter;
+
+pub const NR41: u16 = 0xFF20;
+pub const NR44: u16 = 0xFF23;
+
+const DIVISORS: [u32;8] = [8,16,32,48,64,80,96,112];
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Lfsr{
+	bits: u16,
+	width7: bool,
+}
+
+impl Lfsr{
+	//all ones, as triggering the channel leaves it.
+	pub fn new(width7: bool)->Self{
+		Lfsr{bits: 0x7FFF,width7}
+	}
+
+	pub fn clock(&mut self){
+		let feedback=(self.bits^(self.bits>>1))&1;
+		self.bits=self.bits>>1|feedback<<14;
+		if self.width7 {
+			self.bits=self.bits&!(1<<6)|feedback<<6;
+		}
+	}
+
+	//1 when the channel is high.
+	pub fn output(&self)->u8{
+		!self.bits as u8&1
+	}
+}
+
+#[derive(Debug)]
+pub struct Noise{
+	//NR42 upper 5 bits not all 0.
+	dac: bool,
+	enabled: bool,
+	length: LengthCounter,
+	//NR43 fields.
+	shift: u8,
+	width7: bool,
+	divisor: u8,
+	timer: u32,
+	lfsr: Lfsr,
+}
+
+impl Default for Noise{
+	fn default()->Self{
+		Noise{dac: false,enabled: false,length: LengthCounter::new(64),shift: 0,width7: false,divisor: 0,timer: 0,
+			lfsr: Lfsr::new(false)}
+	}
+}
+
+impl Noise{
+	pub fn enabled(&self)->bool{
+		self.enabled
+	}
+
+	pub fn lfsr(&self)->Lfsr{
+		self.lfsr
+	}
+
+	pub fn length(&self)->&LengthCounter{
+		&self.length
+	}
+
+	pub fn load_length(&mut self,data: u8){
+		self.length.load(data);
+	}
+
+	//cycles between LFSR clocks, None when the shift stops it.
+	fn period(&self)->Option<u32>{
+		if self.shift>=14 {None} else {Some(DIVISORS[self.divisor as usize]<<self.shift)}
+	}
+
+	pub fn write_register(&mut self,address: u16,data: u8,extra_clock: bool){
+		match address{
+			0xFF20 => self.load_length(data),
+			0xFF21 => {
+				self.dac=data&0xF8!=0;
+				if !self.dac {
+					self.enabled=false;
+				}
+			},
+			0xFF22 => {
+				self.shift=data>>4;
+				self.width7=data&0x08!=0;
+				self.divisor=data&0x07;
+				self.lfsr.width7=self.width7;
+			},
+			0xFF23 => {
+				let alive=self.length.write_control(data,extra_clock);
+				if data&0x80!=0 {
+					self.trigger();
+				}
+				if !alive {
+					self.enabled=false;
+				}
+			},
+			_      => {},
+		}
+	}
+
+	fn trigger(&mut self){
+		self.enabled=self.dac;
+		self.lfsr=Lfsr::new(self.width7);
+		self.timer=self.period().unwrap_or(0);
+	}
+
+	pub fn clock_length(&mut self){
+		if !self.length.clock() {
+			self.enabled=false;
+		}
+	}
+
+	//the length counter survives.
+	pub fn power_off(&mut self){
+		*self=Noise{length: self.length,..Noise::default()};
+		self.length.power_off();
+	}
+
+	pub fn step(&mut self,cycles: u32){
+		let period=match self.period(){
+			Some(period) if self.enabled => period,
+			_                            => return,
+		};
+		let mut cycles=cycles;
+		while cycles>=self.timer{
+			cycles-=self.timer;
+			self.timer=period;
+			self.lfsr.clock();
+		}
+		self.timer-=cycles;
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{Lfsr,Noise};
+
+	fn outputs(lfsr: &mut Lfsr,count: usize)->String{
+		(0..count).map(|_| {
+			lfsr.clock();
+			if lfsr.output()==1 {'1'} else {'0'}
+		}).collect()
+	}
+
+	//the bits above 6 make all ones a transient state in 7 bit mode,
+	//so count from the first clock.
+	fn period(width7: bool)->usize{
+		let mut start=Lfsr::new(width7);
+		start.clock();
+		let mut lfsr=start;
+		lfsr.clock();
+		let mut clocks=1;
+		while lfsr!=start{
+			lfsr.clock();
+			clocks+=1;
+		}
+		clocks
+	}
+
+	#[test]
+	fn lfsr_patterns(){
+		assert_eq!(outputs(&mut Lfsr::new(false),16),"0000000000000011");
+		assert_eq!(outputs(&mut Lfsr::new(true),16),"0000001111110111");
+		assert_eq!(period(false),32767);
+		assert_eq!(period(true),127);
+	}
+
+	#[test]
+	fn clocked_by_divisor_and_shift(){
+		let mut noise=Noise::default();
+		noise.write_register(0xFF21,0xF0,false);
+		//r=3 (48), s=2: every 192 cycles, 7 bit mode
+		noise.write_register(0xFF22,0x2B,false);
+		noise.write_register(0xFF23,0x80,false);
+		assert!(noise.enabled());
+		noise.step(191);
+		assert_eq!(noise.lfsr(),Lfsr::new(true));
+		noise.step(1+192*5);
+		let mut expected=Lfsr::new(true);
+		for _ in 0..6{
+			expected.clock();
+		}
+		assert_eq!(noise.lfsr(),expected);
+		//shift 14 stops the clock
+		noise.write_register(0xFF22,0xE0,false);
+		noise.step(10_000);
+		expected.width7=false;
+		assert_eq!(noise.lfsr(),expected);
+	}
+}
@@ -0,0 +1,66 @@
+//Channels 1 and 2. Only what the registers expose for now: the DAC,
+//whether it's playing and its length counter.
+
+use gb::apu::length::LengthCounter;
+
+#[derive(Debug)]
+pub struct Square{
+	//NRx0 address, NR10 or the unused 0xFF15 for channel 2.
+	base: u16,
+	//NRx2 upper 5 bits not all 0.
+	dac: bool,
+	enabled: bool,
+	length: LengthCounter,
+}
+
+impl Square{
+	pub fn new(base: u16)->Self{
+		Square{base,dac: false,enabled: false,length: LengthCounter::new(64)}
+	}
+
+	pub fn enabled(&self)->bool{
+		self.enabled
+	}
+
+	pub fn length(&self)->&LengthCounter{
+		&self.length
+	}
+
+	pub fn load_length(&mut self,data: u8){
+		self.length.load(data);
+	}
+
+	pub fn write_register(&mut self,address: u16,data: u8,extra_clock: bool){
+		match address-self.base{
+			1 => self.load_length(data),
+			2 => {
+				self.dac=data&0xF8!=0;
+				if !self.dac {
+					self.enabled=false;
+				}
+			},
+			4 => {
+				let alive=self.length.write_control(data,extra_clock);
+				if data&0x80!=0 {
+					self.enabled=self.dac;
+				}
+				if !alive {
+					self.enabled=false;
+				}
+			},
+			_ => {},
+		}
+	}
+
+	pub fn clock_length(&mut self){
+		if !self.length.clock() {
+			self.enabled=false;
+		}
+	}
+
+	pub fn power_off(&mut self){
+		self.dac=false;
+		self.enabled=false;
+		self.length.power_off();
+	}
+}
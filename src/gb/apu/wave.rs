@@ -0,0 +1,197 @@
+//Channel 3, plays the 32 4-bit samples in wave ram (0xFF30-0xFF3F).
+//
+//While it's playing the cpu can't get at wave ram freely: on a DMG an
+//access only reaches the byte the channel is reading, and only when it
+//lands on the same cycle as the channel's own fetch. Any other read
+//gives 0xFF and other writes are lost. We only learn about accesses
+//after the instruction that did them, so "the same cycle" is taken as
+//within FETCH_WINDOW cycles of the fetch.
+
+use gb::apu::length::LengthCounter;
+
+pub const NR30: u16 = 0xFF1A;
+pub const NR34: u16 = 0xFF1E;
+pub const RAM_START: u16 = 0xFF30;
+pub const RAM_END: u16 = 0xFF3F;
+
+const FETCH_WINDOW: u32 = 2;
+
+#[derive(Debug)]
+pub struct Wave{
+	//NR30 bit 7, the channel can't play with it off.
+	dac: bool,
+	//11 bits from NR33 and NR34.
+	frequency: u16,
+	enabled: bool,
+	length: LengthCounter,
+	ram: [u8;16],
+	//cycles until the next sample.
+	timer: u32,
+	//sample being played, 0-31.
+	position: u8,
+	//the byte holding it, read when the channel moved to it.
+	sample_byte: u8,
+	//cycles since that read.
+	since_fetch: u32,
+}
+
+impl Default for Wave{
+	fn default()->Self{
+		Wave{dac: false,frequency: 0,enabled: false,length: LengthCounter::new(256),ram: [0;16],
+			timer: 0,position: 0,sample_byte: 0,since_fetch: 0}
+	}
+}
+
+impl Wave{
+	pub fn enabled(&self)->bool{
+		self.enabled
+	}
+
+	pub fn length(&self)->&LengthCounter{
+		&self.length
+	}
+
+	pub fn load_length(&mut self,data: u8){
+		self.length.load(data);
+	}
+
+	pub fn position(&self)->u8{
+		self.position
+	}
+
+	//cycles per sample.
+	fn period(&self)->u32{
+		(2048-self.frequency as u32)*2
+	}
+
+	pub fn write_register(&mut self,address: u16,data: u8,extra_clock: bool){
+		match address{
+			0xFF1A => {
+				self.dac=data&0x80!=0;
+				if !self.dac {
+					self.enabled=false;
+				}
+			},
+			0xFF1B => self.load_length(data),
+			0xFF1D => self.frequency=self.frequency&0x700|data as u16,
+			0xFF1E => {
+				self.frequency=self.frequency&0xFF|((data&0x07) as u16)<<8;
+				let alive=self.length.write_control(data,extra_clock);
+				if data&0x80!=0 {
+					self.trigger();
+				}
+				if !alive {
+					self.enabled=false;
+				}
+			},
+			_      => {},
+		}
+	}
+
+	fn trigger(&mut self){
+		self.enabled=self.dac;
+		self.position=0;
+		self.timer=self.period();
+		self.since_fetch=FETCH_WINDOW;
+	}
+
+	pub fn clock_length(&mut self){
+		if !self.length.clock() {
+			self.enabled=false;
+		}
+	}
+
+	//wave ram and the length counter survive.
+	pub fn power_off(&mut self){
+		*self=Wave{ram: self.ram,length: self.length,..Wave::default()};
+		self.length.power_off();
+	}
+
+	pub fn step(&mut self,cycles: u32){
+		if !self.enabled {
+			return;
+		}
+		self.since_fetch=self.since_fetch.saturating_add(cycles);
+		let mut cycles=cycles;
+		while cycles>=self.timer{
+			cycles-=self.timer;
+			self.timer=self.period();
+			self.position=(self.position+1)%32;
+			self.sample_byte=self.ram[self.position as usize/2];
+			self.since_fetch=cycles;
+		}
+		self.timer-=cycles;
+	}
+
+	//the byte the cpu can reach right now, if any.
+	fn accessible(&self,address: u16)->Option<usize>{
+		if !self.enabled {
+			Some((address-RAM_START) as usize)
+		}
+		else if self.since_fetch<FETCH_WINDOW {
+			Some(self.position as usize/2)
+		}
+		else {
+			None
+		}
+	}
+
+	pub fn read_ram(&self,address: u16)->u8{
+		self.accessible(address).map_or(0xFF,|i| self.ram[i])
+	}
+
+	pub fn write_ram(&mut self,address: u16,data: u8){
+		if let Some(i)=self.accessible(address) {
+			self.ram[i]=data;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::Wave;
+
+	fn playing(frequency: u16)->Wave{
+		let mut wave=Wave::default();
+		for (i,address) in (0xFF30..=0xFF3F).enumerate(){
+			wave.write_ram(address,i as u8*0x11);
+		}
+		wave.write_register(0xFF1A,0x80,false);
+		wave.write_register(0xFF1D,frequency as u8,false);
+		wave.write_register(0xFF1E,0x80|(frequency>>8) as u8,false);
+		wave
+	}
+
+	#[test]
+	fn ram_is_free_while_stopped(){
+		let mut wave=Wave::default();
+		wave.write_ram(0xFF35,0x5A);
+		assert_eq!(wave.read_ram(0xFF35),0x5A);
+		//no dac, triggering doesn't start it
+		wave.write_register(0xFF1E,0x80,false);
+		assert!(!wave.enabled());
+		assert_eq!(wave.read_ram(0xFF35),0x5A);
+	}
+
+	#[test]
+	fn playing_channel_only_exposes_the_byte_it_just_read(){
+		//frequency 2044: a sample every 8 cycles
+		let mut wave=playing(2044);
+		assert!(wave.enabled());
+		assert_eq!(wave.read_ram(0xFF30),0xFF);
+		wave.step(8);
+		assert_eq!(wave.position(),1);
+		//any address reads the byte holding sample 1
+		assert_eq!(wave.read_ram(0xFF3F),0x00);
+		wave.step(12);
+		assert_eq!(wave.position(),2);
+		assert_eq!(wave.read_ram(0xFF30),0xFF);
+		wave.step(4);
+		assert_eq!(wave.position(),3);
+		assert_eq!(wave.read_ram(0xFF30),0x11);
+		wave.write_ram(0xFF30,0xAB);
+		wave.write_register(0xFF1A,0x00,false);
+		assert_eq!(wave.read_ram(0xFF31),0xAB);
+	}
+}
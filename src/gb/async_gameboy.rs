@@ -0,0 +1,95 @@
+//A GameBoy as a future, for async servers running many instances. Every
+//poll runs one frame and yields, input comes in and frames go out through
+//tokio channels. Hooks and serial devices aren't Send, so it runs on a
+//LocalSet (or any single threaded executor).
+//
+//Outputs are unbounded: a consumer that can't keep up should drop the
+//receiver and start over rather than let them pile up.
+use gb::gameboy::{GameBoy,FrameOutput};
+use gb::joypad::JoypadState;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context,Poll};
+use tokio::sync::mpsc::{self,UnboundedReceiver,UnboundedSender};
+use tokio::sync::mpsc::error::TryRecvError;
+
+pub struct AsyncGameBoy{
+	//None once the future finished and gave it back.
+	gb: Option<GameBoy>,
+	input: UnboundedReceiver<JoypadState>,
+	//the latest keys sent, held until new ones arrive.
+	keys: JoypadState,
+	output: UnboundedSender<FrameOutput>,
+}
+
+impl AsyncGameBoy{
+	//also returns the ends the caller keeps: where to send the keys held
+	//and where finished frames arrive. Dropping either stops the future,
+	//which resolves to the GameBoy.
+	pub fn new(gb: GameBoy) -> (AsyncGameBoy,UnboundedSender<JoypadState>,UnboundedReceiver<FrameOutput>) {
+		let (input_tx,input)=mpsc::unbounded_channel();
+		let (output,output_rx)=mpsc::unbounded_channel();
+		(AsyncGameBoy{gb: Some(gb),input,keys: JoypadState::default(),output},input_tx,output_rx)
+	}
+}
+
+impl Future for AsyncGameBoy{
+	type Output=GameBoy;
+
+	fn poll(self: Pin<&mut Self>,cx: &mut Context) -> Poll<GameBoy> {
+		let this=self.get_mut();
+		let gb=this.gb.as_mut().expect("AsyncGameBoy polled after it finished");
+		//only the latest state matters, the frame about to run reads it
+		loop {
+			match this.input.try_recv(){
+				Ok(keys)                        => this.keys=keys,
+				Err(TryRecvError::Empty)        => break,
+				Err(TryRecvError::Disconnected) => return Poll::Ready(this.gb.take().unwrap()),
+			}
+		}
+		if this.output.is_closed() || this.output.send(gb.run_frame(this.keys)).is_err() {
+			return Poll::Ready(this.gb.take().unwrap());
+		}
+		//yield so other instances get to run their frame
+		cx.waker().wake_by_ref();
+		Poll::Pending
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::AsyncGameBoy;
+	use gb::gameboy::GameBoy;
+	use gb::interconnect::RamInit;
+	use gb::joypad::JoypadState;
+	use gb::testrom;
+	use std::future::Future;
+	use std::pin::Pin;
+	use std::task::{Context,Poll,Waker};
+
+	#[test]
+	fn one_frame_per_poll(){
+		let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+		gb.set_verbose(false);
+		let (mut instance,keys,mut frames)=AsyncGameBoy::new(gb);
+		let mut cx=Context::from_waker(Waker::noop());
+		for _ in 0..2{
+			assert!(Pin::new(&mut instance).poll(&mut cx).is_pending());
+		}
+		keys.send(JoypadState::START).unwrap();
+		assert!(Pin::new(&mut instance).poll(&mut cx).is_pending());
+		let numbers: Vec<u64>=(0..3).map(|_| frames.try_recv().unwrap().frame).collect();
+		assert_eq!(numbers,vec![0,1,2]);
+		drop(frames);
+		match Pin::new(&mut instance).poll(&mut cx){
+			Poll::Ready(gb) => {
+				//frames end as VBlank starts, the third one's just did
+				assert_eq!(gb.frame(),2);
+				assert_eq!(gb.interconnect().joypad().state(),JoypadState::START);
+			},
+			Poll::Pending   => panic!("still running without a receiver"),
+		}
+	}
+}
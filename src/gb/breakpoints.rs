@@ -0,0 +1,137 @@
+//Breakpoints on pc with a list of actions run when they hit, gdb's
+//`commands`, to log state at some point of a game without stopping there.
+use gb::cpu::CpuState;
+use gb::regwatch::RegisterWatch;
+use gb::memory_map;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum Action{
+	//print a register, pair or flag, as named for RegisterWatch.
+	Print(RegisterWatch),
+	//print every register.
+	Registers,
+	//hexdump of start..=end.
+	DumpMemory(u16,u16),
+	//keep running instead of stopping. Ends the list, like in gdb.
+	Continue,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Breakpoint{
+	pub pc: u16,
+	pub actions: Vec<Action>,
+}
+
+impl Breakpoint{
+	//stops the run with no actions.
+	pub fn new(pc: u16)->Self{
+		Breakpoint{pc,actions: Vec::new()}
+	}
+
+	//parses actions separated by ';' or newlines: "print A", "regs",
+	//"dumpmem C000:C010" (hex, inclusive, C000-C010 works too) and
+	//"continue". Braces around the list are ignored so
+	//"{ print A; continue }" works as well.
+	pub fn with_actions(pc: u16,script: &str)->Result<Self,String>{
+		let script=script.trim();
+		let script=script.strip_prefix('{').unwrap_or(script);
+		let script=script.strip_suffix('}').unwrap_or(script);
+		let mut actions=Vec::new();
+		for command in script.split(&[';','\n'][..]).map(str::trim).filter(|c| !c.is_empty()){
+			if actions.last()==Some(&Action::Continue) {
+				return Err(format!("\"{}\" comes after continue",command));
+			}
+			actions.push(parse_action(command)?);
+		}
+		Ok(Breakpoint{pc,actions})
+	}
+
+	pub fn continues(&self)->bool{
+		self.actions.last()==Some(&Action::Continue)
+	}
+
+	//output of the actions, `state` being the registers at the breakpoint.
+	pub fn run_actions(&self,state: &CpuState,peek: &dyn Fn(u16)->Option<u8>)->Vec<String>{
+		let mut lines=Vec::new();
+		for action in &self.actions{
+			match *action{
+				Action::Print(ref register) => {
+					let value=register.value(state).unwrap();
+					let digits=match register.name(){
+						"ZF" | "NF" | "HF" | "CF" => 1,
+						name if name.len()==1     => 2,
+						_                         => 4,
+					};
+					lines.push(format!("{}={:02$X}",register.name(),value,digits));
+				},
+				Action::Registers => lines.push(state.to_string()),
+				Action::DumpMemory(start,end) => {
+					lines.extend(memory_map::format_memory_dump(start,end,peek));
+				},
+				Action::Continue => {},
+			}
+		}
+		lines
+	}
+}
+
+fn parse_action(command: &str)->Result<Action,String>{
+	let mut words=command.split_whitespace();
+	let name=words.next().unwrap_or("");
+	let argument=words.next();
+	let action=match (name,argument){
+		("print",Some(register)) => Action::Print(RegisterWatch::parse(register)
+			.filter(|_| !register.contains('='))
+			.ok_or_else(|| format!("Unknown register {}",register))?),
+		("regs",None)            => Action::Registers,
+		("dumpmem",Some(range))  => {
+			let (start,end)=parse_range(range).ok_or_else(|| format!("Bad range {}, use C000:C010",range))?;
+			Action::DumpMemory(start,end)
+		},
+		("continue",None)        => Action::Continue,
+		_                        => return Err(format!("Unknown action \"{}\"",command)),
+	};
+	if words.next().is_some() {
+		return Err(format!("Too many arguments in \"{}\"",command));
+	}
+	Ok(action)
+}
+
+fn parse_range(range: &str)->Option<(u16,u16)>{
+	let mut parts=range.splitn(2,&[':','-'][..]);
+	let hex=|text: &str| {
+		let text=text.trim_start_matches("0x");
+		u16::from_str_radix(text,16).ok()
+	};
+	let start=hex(parts.next()?)?;
+	let end=hex(parts.next()?)?;
+	if start<=end {Some((start,end))} else {None}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{Breakpoint,Action};
+	use gb::cpu::CpuState;
+
+	#[test]
+	fn parses_gdb_like_lists(){
+		let breakpoint=Breakpoint::with_actions(0x150,"{ print A; dumpmem 0xC000:0xC010; continue }").unwrap();
+		assert_eq!(breakpoint.actions.len(),3);
+		assert_eq!(breakpoint.actions[1],Action::DumpMemory(0xC000,0xC010));
+		assert!(breakpoint.continues());
+		assert!(!Breakpoint::with_actions(0x150,"print A").unwrap().continues());
+		assert!(Breakpoint::with_actions(0x150,"print Q").is_err());
+		assert!(Breakpoint::with_actions(0x150,"continue; print A").is_err());
+		assert!(Breakpoint::with_actions(0x150,"dumpmem C010:C000").is_err());
+	}
+
+	#[test]
+	fn runs_actions(){
+		let breakpoint=Breakpoint::with_actions(0x150,"print A; print HL; print ZF; dumpmem C000:C001").unwrap();
+		let state=CpuState{a:0x3C,f:0x80,b:0,c:0,d:0,e:0,h:0xC0,l:0x01,sp:0xFFFE,pc:0x150};
+		let lines=breakpoint.run_actions(&state,&|a| Some(a as u8));
+		assert_eq!(&lines[..3],&["A=3C","HL=C001","ZF=1"]);
+		assert!(lines[3].starts_with("C000 | 00 01 "),"{}",lines[3]);
+	}
+}
@@ -1,16 +1,30 @@
 //TODO change integer size to use the least number of casts!!!!.
 //Codeslinger for reference.
-use std::fmt::{Display,Result,Formatter,Debug};
+use gb::compat::{self,Feature};
+use gb::save;
+use gb::rtc::Rtc;
+use gb::rumble::Rumble;
 
-const RAM_BANK_SIZE:u8 = 0x2000;
-const ROM_BANK_SIZE:u8 = 0x4000;
+const RAM_BANK_SIZE:u16 = 0x2000;
+const ROM_BANK_SIZE:u16 = 0x4000;
+
+//bitmap the boot rom checks at 0x104-0x133.
+pub const NINTENDO_LOGO: [u8;48] = [
+	0xCE,0xED,0x66,0x66,0xCC,0x0D,0x00,0x0B,0x03,0x73,0x00,0x83,0x00,0x0C,0x00,0x0D,
+	0x00,0x08,0x11,0x1F,0x88,0x89,0x00,0x0E,0xDC,0xCC,0x6E,0xE6,0xDD,0xDD,0xD9,0x99,
+	0xBB,0xBB,0x67,0x63,0x6E,0x0E,0xEC,0xCC,0xDD,0xDC,0x99,0x9F,0xBB,0xB9,0x33,0x3E,
+];
+
+//where raw binaries get loaded, right after the header.
+pub const RAW_LOAD_ADDRESS: u16 = 0x150;
 
 #[derive(Debug)]
 enum MemoryBankController{
 	Mbc0,
 	Mbc1,
 	Mbc2,
-	Mbc3
+	Mbc3,
+	Mbc5,
 }
 
 pub struct Cartridge{
@@ -25,6 +39,12 @@ pub struct Cartridge{
 	current_ram: 	u16,
 	enable_ram:		bool,
 	rom_mode:		bool,
+	rtc:			Option<Rtc>,
+	//MBC3 rtc register mapped at 0xA000-0xBFFF instead of ram.
+	rtc_select:		Option<u8>,
+	rumble:			Option<Rumble>,
+	//ram written since the flag was last taken.
+	ram_dirty:		bool,
 
 }
 
@@ -48,6 +68,10 @@ impl Cartridge{
 			current_ram: 	0,
 			enable_ram:  	false,
 			rom_mode:		false,
+			rtc:			None,
+			rtc_select:		None,
+			rumble:			None,
+			ram_dirty:		false,
 
 		};
 		ctd.init_cartridge();
@@ -59,39 +83,161 @@ impl Cartridge{
 		self.set_rom_size();
 		self.set_ram_size();
 		self.init_ram();
+		if let 0x0F | 0x10 = self.cart_type() {
+			self.rtc=Some(Rtc::new());
+		}
+		if let 0x1C..=0x1E = self.cart_type() {
+			self.rumble=Some(Rumble::new());
+		}
+		self.verify_checksums();
+		self.check_compatibility();
+	}
+
+	//the MBC3 clock, for carts that have one.
+	pub fn rtc(&self)->Option<&Rtc>{
+		self.rtc.as_ref()
+	}
+
+	pub fn step(&mut self,cycles: u32){
+		if let Some(ref mut rtc)=self.rtc {
+			rtc.step(cycles);
+		}
+		if let Some(ref mut rumble)=self.rumble {
+			rumble.step(cycles);
+		}
+	}
+
+	//rumble intensity over the last frame, when it changed.
+	pub fn end_frame(&mut self)->Option<f32>{
+		self.rumble.as_mut().and_then(|r| r.end_frame())
+	}
+
+	//title from the header, the CGB flag byte is excluded when set.
+	pub fn title(&self)->String{
+		let end=if self.rom[0x143]&0x80!=0 {0x143} else {0x144};
+		self.rom[0x134..end].iter()
+			.take_while(|&&c| c!=0)
+			.map(|&c| c as char)
+			.collect()
+	}
+
+	//whether ram survives power off.
+	pub fn has_battery(&self)->bool{
+		matches!(self.cart_type(),0x03 | 0x06 | 0x09 | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E)
+	}
+
+	pub fn ram(&self)->&[u8]{
+		&self.ram
+	}
+
+	//whether ram was written since the last call, i.e. the save needs
+	//flushing.
+	pub fn take_ram_dirty(&mut self)->bool{
+		::std::mem::replace(&mut self.ram_dirty,false)
+	}
+
+	pub fn ram_bank_count(&self)->usize{
+		self.ram_banks as usize
+	}
+
+	//a ram bank whether or not it's the one mapped at 0xA000, for tooling.
+	pub fn ram_bank(&self,bank: usize)->Option<&[u8]>{
+		let size=self.ram_bank_size as usize;
+		self.ram.get(bank*size..(bank+1)*size)
+	}
+
+	pub fn ram_bank_mut(&mut self,bank: usize)->Option<&mut [u8]>{
+		let size=self.ram_bank_size as usize;
+		self.ram.get_mut(bank*size..(bank+1)*size)
+	}
+
+	//bank mapped at 0x4000-0x7FFF.
+	pub fn current_rom_bank(&self)->usize{
+		self.current_rom as usize
+	}
+
+	//bank mapped at 0xA000-0xBFFF.
+	pub fn current_ram_bank(&self)->usize{
+		self.current_ram as usize
+	}
+
+	//replaces ram with a save, resized to fit.
+	pub fn load_ram(&mut self,data: &[u8]){
+		self.ram=save::fit(data,self.ram.len());
+	}
+
+	pub fn cart_type(&self)->u8{
+		self.rom[0x147]
+	}
+
+	pub fn header_checksum(&self)->u8{
+		self.rom[0x14D]
+	}
+
+	//0x14E-0x14F, big endian. The boot rom doesn't check it.
+	pub fn global_checksum(&self)->u16{
+		(self.rom[0x14E] as u16)<<8 | self.rom[0x14F] as u16
+	}
+
+	pub fn header_checksum_ok(&self)->bool{
+		compute_header_checksum(&self.rom)==self.header_checksum()
+	}
+
+	pub fn global_checksum_ok(&self)->bool{
+		compute_global_checksum(&self.rom)==self.global_checksum()
+	}
+
+	//a wrong checksum usually means a bad dump or a hacked rom.
+	fn verify_checksums(&self){
+		if !self.header_checksum_ok() {
+			eprintln!("Warning: header checksum is 0x{:02x} but the header sums to 0x{:02x}, a real Game Boy would refuse to boot this rom (bad dump?)",
+				self.header_checksum(),compute_header_checksum(&self.rom));
+		}
+		if !self.global_checksum_ok() {
+			eprintln!("Warning: global checksum is 0x{:04x} but the rom sums to 0x{:04x} (bad dump or rom hack?)",
+				self.global_checksum(),compute_global_checksum(&self.rom));
+		}
+	}
+
+	pub fn rom_bank_count(&self)->usize{
+		self.rom_banks as usize
+	}
+
+	//warns about features this ROM needs that we don't emulate.
+	fn check_compatibility(&self){
+		let mut features=Vec::new();
+		if self.rom[0x143]==0xC0 {
+			features.push(Feature::Cgb);
+		}
+		let title=self.title();
+		let entry=compat::lookup(&title,self.header_checksum());
+		if let Some(ref entry)=entry{
+			for &feature in &entry.features{
+				if !features.contains(&feature){
+					features.push(feature);
+				}
+			}
+		}
+		for feature in &features{
+			eprintln!("Warning: {} {}, it may not run correctly.",title,feature);
+		}
+		if let Some(note)=entry.and_then(|e| e.note){
+			eprintln!("Warning: {}",note);
+		}
 	}
 
 	fn set_mbc(&mut self){
 		let cart_type=self.rom[0x147];
-		self.mbc=
-			match cart_type{
-				0x00 		=> MemoryBankController::Mbc0,
-				0x01...0x03 => MemoryBankController::Mbc1,
-				0x05...0x06 => MemoryBankController::Mbc2,
-				0x0F...0x13 => MemoryBankController::Mbc3,
-			    _      		=> panic!("Unknown cartridge model 0x{:02x}", cart_type),
- 			}
+		self.mbc=controller(cart_type).unwrap_or_else(||
+			panic!("Unknown cartridge model 0x{:02x}", cart_type)
+		);
 	}
 
 	fn set_rom_size(&mut self){
 		let cart_rom_size=self.rom[0x148];
-		self.rom_banks=
-			match cart_rom_size{
-                0x00 => 2,
-                0x01 => 4,
-                0x02 => 8,
-                0x03 => 16,
-                0x04 => 32,
-                0x05 => 64,
-                0x06 => 128,
-                0x07 => 256,
-                0x08 => 512,
-                //0x52 => 72,
-                //0x53 => 80,
-                //0x54 => 96,
-                //_ => 0
-                _ => panic!("Unsupported number of rom banks 0x{:02x}",cart_rom_size),
-			};
+		self.rom_banks=declared_rom_banks(cart_rom_size).unwrap_or_else(||
+			panic!("Unsupported number of rom banks 0x{:02x}",cart_rom_size)
+		);
 	}
 
 	fn set_ram_size(&mut self){
@@ -102,14 +248,51 @@ impl Cartridge{
 				0x01 => (1,0x800),
 				0x02 =>	(1,0x2000),
 				0x03 => (4,0x2000),
+				0x04 => (16,0x2000),
+				0x05 => (8,0x2000),
 				_    => panic!("Unsupported ram type 0x{:02x}",cart_ram_size),
 			};
 		self.ram_banks=numbanks;
 		self.ram_bank_size=banksize;
+		self.validate_ram_size();
+	}
+
+	//headers don't always agree with the cartridge type, so the type wins.
+	fn validate_ram_size(&mut self){
+		let cart_type=self.cart_type();
+		let has_ram=matches!(cart_type,0x02 | 0x03 | 0x08 | 0x09 | 0x10 | 0x12 | 0x13 | 0x1A | 0x1B | 0x1D | 0x1E);
+		match self.mbc{
+			//MBC2 has 512x4 bits of ram on the chip itself.
+			MemoryBankController::Mbc2 => {
+				if self.ram_banks!=0 {
+					eprintln!("Warning: MBC2 cartridge declares ram size 0x{:02x}, using its internal 512x4 ram instead",self.rom[0x149]);
+				}
+				self.ram_banks=1;
+				self.ram_bank_size=0x200;
+			},
+			_ if !has_ram => {
+				if self.ram_banks!=0 {
+					eprintln!("Warning: cartridge type 0x{:02x} has no ram, ignoring ram size 0x{:02x}",cart_type,self.rom[0x149]);
+				}
+				self.ram_banks=0;
+				self.ram_bank_size=0;
+			},
+			_ if self.ram_banks==0 => {
+				eprintln!("Warning: cartridge type 0x{:02x} has ram but declares none, assuming 8KB",cart_type);
+				self.ram_banks=1;
+				self.ram_bank_size=RAM_BANK_SIZE;
+			},
+			//MBC1 can only address 4 banks.
+			MemoryBankController::Mbc1 if self.ram_banks>4 => {
+				eprintln!("Warning: MBC1 cartridge declares ram size 0x{:02x}, limiting it to 32KB",self.rom[0x149]);
+				self.ram_banks=4;
+			},
+			_ => {},
+		}
 	}
 
 	fn init_ram(&mut self){
-		self.ram=vec![0;(self.ram_bank_size*self.ram_banks) as usize];
+		self.ram=vec![0;self.ram_bank_size as usize*self.ram_banks as usize];
 	}
 
 
@@ -120,49 +303,87 @@ impl Cartridge{
 			MemoryBankController::Mbc1 => self.write_mbc1(address,data),
 			MemoryBankController::Mbc2 => self.write_mbc2(address,data),
 			MemoryBankController::Mbc3 => self.write_mbc3(address,data),
+			MemoryBankController::Mbc5 => self.write_mbc5(address,data),
 		}
 	}
 
 	//TODO: Test thoroughly 
 	pub fn read(&self,address:u16)->u8{
 		match address {
-			0x0000...0x7FFF => {
-				let new_address: isize=(address as isize-0x4000)+(0x4000*self.current_rom as isize);
-				self.rom[new_address as usize]
-			},
-			0xA000...0xBFFF => {
-				let new_address: isize=(address as isize-0xA000)+((self.ram_bank_size*self.current_ram)as isize);
-				self.ram[new_address as usize]
+			0x0000..=0x7FFF => self.rom_offset(address).map_or(0xFF,|i| self.rom[i]),
+			0xA000..=0xBFFF => {
+				if let (Some(register),Some(rtc))=(self.rtc_select,self.rtc.as_ref()) {
+					return rtc.read(register);
+				}
+				self.ram_index(address).map_or(0xFF,|i| self.ram[i])
 			},
 			_               => panic!("Cannot find address 0x{:02x} in cartridge",address),
 
 		}
 	}
 
+	//offset into the rom file mapped at `address` (0x0000-0x7FFF). Banks are
+	//masked by the real size of the dump rather than the header, None past
+	//the end of a truncated dump, which reads like open bus.
+	pub fn rom_offset(&self,address: u16)->Option<usize>{
+		let (bank,offset)=match address{
+			0x0000..=0x3FFF => (0,address),
+			_               => (self.current_rom as usize,address-0x4000),
+		};
+		let bank_size=ROM_BANK_SIZE as usize;
+		let banks=self.rom.len().div_ceil(bank_size).max(1);
+		let index=mirror_bank(bank,banks)*bank_size+offset as usize;
+		if index<self.rom.len() {Some(index)} else {None}
+	}
+
+	pub fn rom(&self)->&[u8]{
+		&self.rom
+	}
+
 	//implement!!
 	fn write_mbc1(&mut self,address: u16,data: u8){
 		match address{
-			0x0000...0x1FFF => self.enable_ram=(data&0xF)==0xA,
-			0x2000...0x3FFF => self.set_rombank_hi_lo(data,SetRomBank::Low),
-			0x4000...0x5FFF => self.set_romram_bank(data), 
-			0x6000...0x7FFF => self.set_romram_mode(data),
-			0xA000...0xBFFF => self.write_ram(address,data),
+			0x0000..=0x1FFF => self.enable_ram=(data&0xF)==0xA,
+			0x2000..=0x3FFF => self.set_rombank_hi_lo(data,SetRomBank::Low),
+			0x4000..=0x5FFF => self.set_romram_bank(data), 
+			0x6000..=0x7FFF => self.set_romram_mode(data),
+			0xA000..=0xBFFF => self.write_ram(address,data),
 			_               => unimplemented!(),
 
 		}
 	}
 
 	//Check various sources for ram enabling
-	fn write_mbc2(&mut self,address: u16,data: u8){
+	fn write_mbc2(&mut self,_address: u16,_data: u8){
 		/*match address{
-			0x000...0x1FFF => self.enable_ram^=(address&0x100)==0, //TEST thoroughly
+			0x000..=0x1FFF => self.enable_ram^=(address&0x100)==0, //TEST thoroughly
 
 
 		}*/
 	}
 
-	//TODO IMPLEMENT MBC3!
-	fn write_mbc3(&mut self,address: u16,data: u8){}
+	fn write_mbc3(&mut self,address: u16,data: u8){
+		match address{
+			0x0000..=0x1FFF => self.enable_ram=(data&0xF)==0xA,
+			0x2000..=0x3FFF => self.current_rom=((data&0x7F) as u16).max(1),
+			0x4000..=0x5FFF => match data{
+				0x00..=0x03 => {
+					self.current_ram=data as u16;
+					self.rtc_select=None;
+				},
+				0x08..=0x0C if self.rtc.is_some() => self.rtc_select=Some(data),
+				_ => {},
+			},
+			0x6000..=0x7FFF => if let Some(ref mut rtc)=self.rtc {
+				rtc.write_latch(data);
+			},
+			0xA000..=0xBFFF => match (self.rtc_select,self.rtc.as_mut()){
+				(Some(register),Some(rtc)) => rtc.write(register,data),
+				_                          => self.write_ram(address,data),
+			},
+			_               => {},
+		}
+	}
 
 	fn set_romram_bank(&mut self,data: u8){
 		if self.rom_mode{
@@ -184,8 +405,8 @@ impl Cartridge{
 		match mode{
 			SetRomBank::High => {
 				self.current_rom&=0x1F;
-				let upper3=data&0xE0;
-				self.current_rom|=data as u16;
+				let upper2=data&0x3;
+				self.current_rom|=(upper2 as u16)<<5;
 			},
 			SetRomBank::Low => {
 				let lower5=data&0x1F;
@@ -199,9 +420,371 @@ impl Cartridge{
 	}
 
 
+	//unlike the others bank 0 can be mapped at 0x4000, and on rumble carts
+	//bit 3 of the ram bank drives the motor.
+	fn write_mbc5(&mut self,address: u16,data: u8){
+		match address{
+			0x0000..=0x1FFF => self.enable_ram=(data&0xF)==0xA,
+			0x2000..=0x2FFF => self.current_rom=(self.current_rom&0x100) | data as u16,
+			0x3000..=0x3FFF => self.current_rom=(self.current_rom&0xFF) | ((data&1) as u16)<<8,
+			0x4000..=0x5FFF => match self.rumble{
+				Some(ref mut rumble) => {
+					rumble.set_motor(data&0x08!=0);
+					self.current_ram=(data&0x07) as u16;
+				},
+				None => self.current_ram=(data&0x0F) as u16,
+			},
+			0x6000..=0x7FFF => {},
+			0xA000..=0xBFFF => self.write_ram(address,data),
+			_               => {},
+		}
+	}
+
 	fn write_ram(&mut self,address: u16,data: u8){
-		let new_address: isize=(address as isize-0xA000)+((self.ram_bank_size*self.current_ram) as isize);
-		self.ram[new_address as usize]=data;
+		if let Some(i)=self.ram_index(address) {
+			self.ram[i]=data;
+			self.ram_dirty=true;
+		}
+	}
+
+	//whether anything answers at `address` (0xA000-0xBFFF), carts without
+	//ram read open bus there.
+	pub fn ram_mapped(&self,address: u16)->bool{
+		(self.rtc_select.is_some() && self.rtc.is_some()) || self.ram_index(address).is_some()
+	}
+
+	//None when the cart has no ram at that address.
+	fn ram_index(&self,address: u16)->Option<usize>{
+		let index=(address-0xA000) as usize+self.ram_bank_size as usize*self.current_ram as usize;
+		if index<self.ram.len() {Some(index)} else {None}
+	}
+
+}
+
+fn controller(cart_type: u8)->Option<MemoryBankController>{
+	match cart_type{
+		0x00 		=> Some(MemoryBankController::Mbc0),
+		0x01..=0x03 => Some(MemoryBankController::Mbc1),
+		0x05..=0x06 => Some(MemoryBankController::Mbc2),
+		0x0F..=0x13 => Some(MemoryBankController::Mbc3),
+		0x19..=0x1E => Some(MemoryBankController::Mbc5),
+		_      		=> None,
+	}
+}
+
+//16KB rom banks the header's rom size byte (0x148) stands for.
+fn declared_rom_banks(rom_size: u8)->Option<u16>{
+	match rom_size{
+		0x00..=0x08 => Some(2<<rom_size),
+		0x52 => Some(72),
+		0x53 => Some(80),
+		0x54 => Some(96),
+		_ => None,
+	}
+}
+
+impl MemoryBankController{
+	//most rom banks it can switch between.
+	fn max_rom_banks(&self)->u16{
+		match *self{
+			MemoryBankController::Mbc0 => 2,
+			MemoryBankController::Mbc1 => 128,
+			MemoryBankController::Mbc2 => 16,
+			MemoryBankController::Mbc3 => 128,
+			MemoryBankController::Mbc5 => 512,
+		}
+	}
+}
+
+//everything about the file size, the header's rom size and the cartridge
+//type that doesn't add up. Cartridge::new copes with most of it by
+//mirroring banks, which hides packaging mistakes in homebrew.
+pub fn header_problems(rom: &[u8])->Vec<String>{
+	const BANK: usize = 0x4000;
+	if rom.len()<0x150 {
+		return vec![format!("the file is {} bytes, too short to hold a header",rom.len())];
+	}
+	let mut problems=Vec::new();
+	if !rom.len().is_multiple_of(BANK) {
+		problems.push(format!("the file is {} bytes, not a whole number of 16KB banks ({} banks and {} bytes)",
+			rom.len(),rom.len()/BANK,rom.len()%BANK));
 	}
+	let banks=declared_rom_banks(rom[0x148]);
+	match banks{
+		None                                     => problems.push(format!("rom size byte 0x{:02x} (0x148) isn't a known size",rom[0x148])),
+		Some(banks) if banks as usize*BANK!=rom.len() => problems.push(format!(
+			"rom size byte 0x{:02x} (0x148) declares {} banks ({} bytes) but the file is {} bytes{}",
+			rom[0x148],banks,banks as usize*BANK,rom.len(),
+			if (banks as usize*BANK)<rom.len() {", the rest can't be reached"} else {", missing banks would read as mirrors"})),
+		Some(_)                                  => {},
+	}
+	match controller(rom[0x147]){
+		None      => problems.push(format!("cartridge type 0x{:02x} (0x147) isn't a known type",rom[0x147])),
+		Some(mbc) => {
+			let file_banks=rom.len().div_ceil(BANK);
+			let most=banks.unwrap_or(0).max(file_banks as u16);
+			if most>mbc.max_rom_banks() {
+				problems.push(format!("cartridge type 0x{:02x} (0x147) is {:?}, which switches at most {} banks, but the rom has {}",
+					rom[0x147],mbc,mbc.max_rom_banks(),most));
+			}
+		},
+	}
+	problems
+}
+
+//addresses of the header logo bytes that differ from NINTENDO_LOGO. The
+//DMG boot rom locks up unless all 48 match (CGB only checks the first 24).
+pub fn logo_mismatches(rom: &[u8])->Vec<u16>{
+	(0..NINTENDO_LOGO.len())
+		.filter(|&i| rom.get(0x104+i)!=Some(&NINTENDO_LOGO[i]))
+		.map(|i| 0x104+i as u16)
+		.collect()
+}
 
-}
\ No newline at end of file
+//the 48x8 pixels encoded by 48 logo bytes, '#' for set pixels. Each byte
+//is two 4 pixel rows of a 4x4 block, high nibble first; the first 24 bytes
+//are the top row of blocks.
+pub fn logo_bitmap(logo: &[u8])->Vec<String>{
+	let mut lines=vec![String::new();8];
+	for (i,&byte) in logo.iter().take(48).enumerate(){
+		for (n,nibble) in [byte>>4,byte&0xF].iter().enumerate(){
+			let line=&mut lines[i/24*4+i%2*2+n];
+			for bit in (0..4).rev(){
+				line.push(if nibble>>bit&1!=0 {'#'} else {' '});
+			}
+		}
+	}
+	lines
+}
+
+//checksum over 0x134-0x14C as computed by the boot rom.
+pub fn compute_header_checksum(rom: &[u8])->u8{
+	rom[0x134..0x14D].iter().fold(0u8,|x,&b| x.wrapping_sub(b).wrapping_sub(1))
+}
+
+//sum of every rom byte but the checksum itself.
+pub fn compute_global_checksum(rom: &[u8])->u16{
+	rom.iter().enumerate()
+		.filter(|&(i,_)| i!=0x14E && i!=0x14F)
+		.fold(0u16,|sum,(_,&b)| sum.wrapping_add(b as u16))
+}
+
+//wraps a headerless binary in a 32KB MBC0 image. The binary is loaded at
+//RAW_LOAD_ADDRESS and the entry point jumps straight to it. Fails when
+//the binary doesn't fit.
+pub fn raw_image(bin: &[u8])->Result<Vec<u8>,String>{
+	let start=RAW_LOAD_ADDRESS as usize;
+	let max=0x8000-start;
+	if bin.len()>max {
+		return Err(format!("Raw binary is {} bytes, at most {} fit in an MBC0 cartridge",bin.len(),max));
+	}
+	let mut rom=vec![0xFF;0x8000];
+	//nop; jp RAW_LOAD_ADDRESS
+	rom[0x100..0x104].copy_from_slice(&[0x00,0xC3,start as u8,(start>>8) as u8]);
+	rom[0x104..0x134].copy_from_slice(&NINTENDO_LOGO);
+	for b in &mut rom[0x134..0x150]{
+		*b=0;
+	}
+	rom[0x134..0x137].copy_from_slice(b"RAW");
+	rom[0x14D]=compute_header_checksum(&rom);
+	rom[start..start+bin.len()].copy_from_slice(bin);
+	let global=compute_global_checksum(&rom);
+	rom[0x14E]=(global>>8) as u8;
+	rom[0x14F]=global as u8;
+	Ok(rom)
+}
+
+//folds a bank number into a rom of `banks` banks. Roms that aren't a power
+//of two in size (i.e. 1.1MB carts) are made of two chips, the smaller one
+//gets mirrored over the rest of the address space.
+fn mirror_bank(bank: usize,banks: usize)->usize{
+	let size=banks.next_power_of_two();
+	let bank=bank&(size-1);
+	if bank<banks {
+		bank
+	}
+	else {
+		let half=size/2;
+		half+mirror_bank(bank-half,banks-half)
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{Cartridge,mirror_bank,raw_image,compute_header_checksum,header_problems,
+		logo_mismatches,logo_bitmap,NINTENDO_LOGO};
+
+	fn rom(cart_type: u8,ram_size: u8)->Vec<u8>{
+		let mut rom=vec![0;0x8000];
+		rom[0x147]=cart_type;
+		rom[0x149]=ram_size;
+		rom
+	}
+
+	#[test]
+	fn mbc2_uses_internal_ram(){
+		let cart=Cartridge::new(rom(0x06,0x03));
+		assert_eq!(cart.ram.len(),0x200);
+	}
+
+	#[test]
+	fn mbc1_ram_limited_to_32kb(){
+		let cart=Cartridge::new(rom(0x03,0x04));
+		assert_eq!(cart.ram.len(),0x8000);
+	}
+
+	#[test]
+	fn ram_size_follows_cart_type(){
+		assert_eq!(Cartridge::new(rom(0x01,0x03)).ram.len(),0);
+		assert_eq!(Cartridge::new(rom(0x03,0x00)).ram.len(),0x2000);
+	}
+
+	#[test]
+	fn mirror_banks(){
+		assert_eq!(mirror_bank(5,8),5);
+		assert_eq!(mirror_bank(9,8),1);
+		assert_eq!(mirror_bank(71,72),71);
+		assert_eq!(mirror_bank(72,72),64);
+		assert_eq!(mirror_bank(127,72),71);
+		assert_eq!(mirror_bank(100,96),68);
+	}
+
+	#[test]
+	fn mbc1_upper_bank_bits(){
+		let mut rom=rom(0x01,0x00);
+		rom[0x148]=0x05;
+		rom.resize(0x4000*64,0);
+		rom[0x4000*0x21]=0x21;
+		let mut cart=Cartridge::new(rom);
+		//rom banking mode
+		cart.write(0x6000,0x00);
+		cart.write(0x2000,0x01);
+		cart.write(0x4000,0x01);
+		assert_eq!(cart.read(0x4000),0x21);
+	}
+
+	#[test]
+	fn odd_rom_size(){
+		let mut rom=rom(0x01,0x00);
+		rom[0x148]=0x52;
+		rom[0x4000]=0x42;
+		let cart=Cartridge::new(rom);
+		assert_eq!(cart.rom_banks,72);
+		assert_eq!(cart.read(0x4000),0x42);
+	}
+
+	#[test]
+	fn strict_header(){
+		assert!(header_problems(&raw_image(&[0x00]).unwrap()).is_empty());
+		//32KB file declaring 64KB
+		let mut rom=rom(0x01,0x00);
+		rom[0x148]=0x01;
+		let problems=header_problems(&rom);
+		assert_eq!(problems.len(),1);
+		assert!(problems[0].contains("declares 4 banks (65536 bytes) but the file is 32768 bytes"));
+		//128KB of MBC0
+		let mut rom=vec![0;0x20000];
+		rom[0x148]=0x02;
+		let problems=header_problems(&rom);
+		assert_eq!(problems.len(),1);
+		assert!(problems[0].contains("Mbc0, which switches at most 2 banks, but the rom has 8"));
+		assert_eq!(header_problems(&[0;0x100]).len(),1);
+		let mut rom=vec![0;0x8001];
+		rom[0x147]=0x04;
+		assert_eq!(header_problems(&rom).len(),3);
+	}
+
+	#[test]
+	fn logo_check(){
+		let mut rom=raw_image(&[0x00]).unwrap();
+		assert!(logo_mismatches(&rom).is_empty());
+		rom[0x110]^=1;
+		assert_eq!(logo_mismatches(&rom),vec![0x110]);
+		assert_eq!(logo_mismatches(&[0xFF;0x120]).len(),48);
+		let bitmap=logo_bitmap(&NINTENDO_LOGO);
+		assert_eq!(bitmap.len(),8);
+		assert!(bitmap.iter().all(|line| line.len()==48));
+		//the N's left stroke
+		assert!(bitmap[..4].iter().all(|line| line.starts_with("##")));
+	}
+
+	#[test]
+	fn raw_binary(){
+		let rom=raw_image(&[0x3C,0x18,0xFE]).unwrap();
+		assert_eq!(rom.len(),0x8000);
+		assert_eq!(rom[0x14D],compute_header_checksum(&rom));
+		let cart=Cartridge::new(rom);
+		assert_eq!(cart.title(),"RAW");
+		assert_eq!(cart.read(0x101),0xC3);
+		assert_eq!(cart.read(0x150),0x3C);
+		assert!(raw_image(&[0;0x8000-0x150]).is_ok());
+		assert!(raw_image(&[0;0x8000-0x150+1]).is_err());
+	}
+
+	#[test]
+	fn mbc3_banks_and_rtc(){
+		let mut rom=rom(0x10,0x03);
+		rom.resize(0x4000*8,0);
+		rom[0x4000*5]=0x55;
+		let mut cart=Cartridge::new(rom);
+		cart.write(0x2000,5);
+		assert_eq!(cart.read(0x4000),0x55);
+		cart.write(0x2000,0);
+		assert_eq!(cart.current_rom,1);
+		cart.write(0x4000,0x02);
+		cart.write(0xA000,0x77);
+		assert_eq!(cart.ram[0x4000],0x77);
+		cart.write(0x4000,0x0A);
+		cart.write(0xA000,13);
+		cart.write(0x6000,0);
+		cart.write(0x6000,1);
+		assert_eq!(cart.read(0xA000),13);
+		assert_eq!(cart.rtc().unwrap().hours(),13);
+	}
+
+	#[test]
+	fn mbc5_rumble(){
+		let mut rom=rom(0x1E,0x03);
+		rom.resize(0x4000*0x102,0);
+		rom[0x4000*0x101]=0x66;
+		let mut cart=Cartridge::new(rom);
+		cart.write(0x2000,0x01);
+		cart.write(0x3000,0x01);
+		assert_eq!(cart.read(0x4000),0x66);
+		cart.write(0x2000,0x00);
+		cart.write(0x3000,0x00);
+		assert_eq!(cart.read(0x4000),cart.read(0x0000));
+		cart.write(0x4000,0x0B);
+		assert_eq!(cart.current_ram,3);
+		cart.step(1000);
+		assert_eq!(cart.end_frame(),Some(1.0));
+	}
+
+	#[test]
+	fn ram_banks_independent_of_mapping(){
+		let mut cart=Cartridge::new(rom(0x13,0x03));
+		assert_eq!(cart.ram_bank_count(),4);
+		cart.ram_bank_mut(2).unwrap()[0x10]=0x99;
+		assert_eq!(cart.current_ram_bank(),0);
+		assert_eq!(cart.read(0xA010),0x00);
+		cart.write(0x4000,0x02);
+		assert_eq!(cart.read(0xA010),0x99);
+		assert_eq!(cart.ram_bank(3).map(|b| b.len()),Some(0x2000));
+		assert!(cart.ram_bank(4).is_none());
+	}
+
+	#[test]
+	fn checksums(){
+		let rom=raw_image(&[0x00,0x18,0xFE]).unwrap();
+		let cart=Cartridge::new(rom.clone());
+		assert!(cart.header_checksum_ok());
+		assert!(cart.global_checksum_ok());
+		let mut bad=rom;
+		bad[0x200]=0x01;
+		bad[0x14D]^=0xFF;
+		let cart=Cartridge::new(bad.clone());
+		assert!(!cart.header_checksum_ok());
+		assert!(!cart.global_checksum_ok());
+	}
+}
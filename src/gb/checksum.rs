@@ -0,0 +1,38 @@
+//FNV-1a, small and good enough to tell two machine states apart.
+
+const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const PRIME: u64 = 0x100000001b3;
+
+pub struct Fnv(u64);
+
+impl Default for Fnv{
+	fn default()->Self{
+		Fnv(OFFSET_BASIS)
+	}
+}
+
+impl Fnv{
+	pub fn write(&mut self,bytes: &[u8]){
+		for &b in bytes{
+			self.0=(self.0^b as u64).wrapping_mul(PRIME);
+		}
+	}
+
+	pub fn finish(&self)->u64{
+		self.0
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::Fnv;
+
+	#[test]
+	fn known_values(){
+		assert_eq!(Fnv::default().finish(),0xcbf29ce484222325);
+		let mut fnv=Fnv::default();
+		fnv.write(b"a");
+		assert_eq!(fnv.finish(),0xaf63dc4c8601ec8c);
+	}
+}
@@ -0,0 +1,93 @@
+//Per-game compatibility database (see compat.toml) used to warn about
+//ROMs that need features we don't emulate yet.
+use toml::Value;
+use std::fmt::{Display,Result,Formatter};
+
+const DATABASE: &str = include_str!("compat.toml");
+
+#[derive(Debug,PartialEq,Clone,Copy)]
+pub enum Feature{
+	Cgb,
+	StatBug,
+}
+
+impl Feature{
+	fn from_name(name: &str)->Option<Feature>{
+		match name{
+			"cgb"      => Some(Feature::Cgb),
+			"stat-bug" => Some(Feature::StatBug),
+			_          => None,
+		}
+	}
+}
+
+impl Display for Feature{
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		let description=
+			match *self{
+				Feature::Cgb     => "needs Game Boy Color hardware",
+				Feature::StatBug => "relies on the DMG STAT write bug",
+			};
+		write!(f,"{}",description)
+	}
+}
+
+//a matching database entry.
+#[derive(Debug)]
+pub struct Entry{
+	pub features: Vec<Feature>,
+	pub note: Option<String>,
+}
+
+//looks up a game by title and header checksum.
+pub fn lookup(title: &str,header_checksum: u8)->Option<Entry>{
+	find(DATABASE,title,header_checksum)
+}
+
+fn find(database: &str,title: &str,header_checksum: u8)->Option<Entry>{
+	let database=database.parse::<Value>()
+		.unwrap_or_else(|e| panic!("Malformed compatibility database: {}",e));
+	let games=database.get("game").and_then(|g| g.as_array())?;
+	games.iter()
+		.find(|game|
+			game.get("title").and_then(|t| t.as_str())==Some(title) &&
+			game.get("header_checksum").and_then(|c| c.as_integer())==Some(header_checksum as i64))
+		.map(|game| Entry{
+			features: game.get("features")
+				.and_then(|f| f.as_array())
+				.map(|f| f.iter()
+					.filter_map(|name| name.as_str().and_then(Feature::from_name))
+					.collect())
+				.unwrap_or_default(),
+			note: game.get("note").and_then(|n| n.as_str()).map(|n| n.to_string()),
+		})
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{DATABASE,lookup,find,Feature};
+	use toml::Value;
+
+	#[test]
+	fn database_parses(){
+		assert!(DATABASE.parse::<Value>().is_ok());
+	}
+
+	#[test]
+	fn matches_title_and_checksum(){
+		let database="[[game]]\ntitle = \"SOME GAME\"\nheader_checksum = 0x3C\n\
+			features = [\"stat-bug\", \"cgb\"]\nnote = \"Flickers.\"\n";
+		let entry=find(database,"SOME GAME",0x3C).unwrap();
+		assert_eq!(entry.features,vec![Feature::StatBug,Feature::Cgb]);
+		assert_eq!(entry.note.as_deref(),Some("Flickers."));
+		//another revision, or a hack keeping the title
+		assert!(find(database,"SOME GAME",0x3D).is_none());
+		assert!(find(database,"SOME GAME 2",0x3C).is_none());
+	}
+
+	#[test]
+	fn unknown_game(){
+		assert!(lookup("NOT A GAME",0x00).is_none());
+	}
+}
@@ -0,0 +1,53 @@
+//Which rom bytes were executed and which were read as data. Offsets are
+//into the rom file so banked code is told apart.
+//
+//Exported as a code/data log: one byte per rom byte, bit 0 set when the
+//byte was fetched as an opcode and bit 1 when it was read as data.
+
+pub const EXECUTED: u8 = 0x01;
+pub const READ: u8 = 0x02;
+
+#[derive(Debug,Clone)]
+pub struct Coverage{
+	flags: Vec<u8>,
+}
+
+impl Coverage{
+	pub fn new(rom_size: usize)->Self{
+		Coverage{flags: vec![0;rom_size]}
+	}
+
+	pub fn mark(&mut self,offset: usize,flag: u8){
+		self.flags[offset]|=flag;
+	}
+
+	pub fn flags(&self,offset: usize)->u8{
+		self.flags[offset]
+	}
+
+	//bytes executed and bytes read.
+	pub fn totals(&self)->(usize,usize){
+		self.flags.iter().fold((0,0),|(e,r),&f|
+			(e+(f&EXECUTED!=0) as usize,r+(f&READ!=0) as usize))
+	}
+
+	pub fn code_data_log(&self)->&[u8]{
+		&self.flags
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{Coverage,EXECUTED,READ};
+
+	#[test]
+	fn totals(){
+		let mut coverage=Coverage::new(4);
+		coverage.mark(0,EXECUTED);
+		coverage.mark(1,READ);
+		coverage.mark(1,EXECUTED);
+		assert_eq!(coverage.totals(),(2,1));
+		assert_eq!(coverage.code_data_log(),&[1,3,0,0]);
+	}
+}
@@ -1,10 +1,28 @@
 use gb::interconnect::*;
 use gb::opcode::Opcode;
-use num::FromPrimitive;
 use gb::register::Register;
+use gb::hooks::{Hooks,Hook,HookAction};
 
 //FINISH TESTS!!!
 
+type Instruction=fn(&mut Cpu,&mut Interconnect)->usize;
+
+//indexed by opcode, so dispatch is a single indirect call instead of
+//decoding into an Opcode and matching on it.
+const INSTRUCTIONS: [Instruction;256]={
+	let mut table=[Cpu::unknown as Instruction;256];
+	table[Opcode::Nop as usize]=Cpu::nop;
+	table[Opcode::LdBcD16 as usize]=Cpu::unimplemented;
+	table[Opcode::DecBc as usize]=Cpu::dec_bc;
+	table[Opcode::DecH as usize]=Cpu::dec_h;
+	table[Opcode::Cpl as usize]=Cpu::cpl;
+	table[Opcode::LdCB as usize]=Cpu::ld_c_b;
+	table[Opcode::LdCC as usize]=Cpu::ld_c_c;
+	table[Opcode::LdCD as usize]=Cpu::ld_c_d;
+	table[Opcode::JpA16 as usize]=Cpu::jp_a16;
+	table
+};
+
 #[derive(Debug)]
 pub struct Cpu{
 	//Program counter
@@ -19,9 +37,28 @@ pub struct Cpu{
 
 	flags: Flags,
 
+	//print every opcode as it runs.
+	verbose: bool,
+
+	hooks: Hooks,
 }
 
-#[derive(Debug)]
+//plain copy of the registers for tooling (traces, snapshots...).
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct CpuState{
+	pub a: u8,
+	pub f: u8,
+	pub b: u8,
+	pub c: u8,
+	pub d: u8,
+	pub e: u8,
+	pub h: u8,
+	pub l: u8,
+	pub sp: u16,
+	pub pc: u16,
+}
+
+#[derive(Debug,PartialEq)]
 struct Flags{
 	z: bool,
 	n: bool,
@@ -29,160 +66,270 @@ struct Flags{
 	c: bool
 }
 
+//F as a byte: ZNHC in the high nibble, the low one always reads 0.
+impl Flags{
+	fn from_byte(f: u8)->Self{
+		Flags{z: f&0x80!=0,n: f&0x40!=0,h: f&0x20!=0,c: f&0x10!=0}
+	}
+
+	fn to_byte(&self)->u8{
+		(self.z as u8)<<7 | (self.n as u8)<<6 | (self.h as u8)<<5 | (self.c as u8)<<4
+	}
+}
+
+impl Default for Cpu{
+	fn default()->Self{
+		Cpu::new()
+	}
+}
+
 //Get rid of all the sets? Good or bad practice? DOIT!
 impl Cpu{
 
-	//set zero flag
-	fn set_zero_flag(&mut self,bit: bool){
-		self.flags.z=bit;
-	}
 	//set subtract flag
+	#[inline]
 	fn set_subtract_flag(&mut self,bit: bool){
 		self.flags.n=bit;
 	}
 	//set half carry flag
+	#[inline]
 	fn set_half_carry_flag(&mut self,bit: bool){
 		self.flags.h=bit;
 	}
-	//set carry flag
-	fn set_carry_flag(&mut self,bit: bool){
-		self.flags.c=bit;
-	}
-
 	//set regs
+	#[inline]
 	fn set_reg_a(&mut self,num: u8){
 		self.regs_af.set_hi(num);
 	}
 
+	#[inline]
 	fn set_reg_b(&mut self,num: u8){
 		self.regs_bc.set_hi(num);
 	}
 
+	#[inline]
 	fn set_reg_c(&mut self,num: u8){
 		self.regs_bc.set_lo(num);
 	}
 
+	#[inline]
 	fn set_reg_d(&mut self,num: u8){
 		self.regs_de.set_hi(num);
 	}
 
+	#[inline]
 	fn set_reg_e(&mut self,num: u8){
 		self.regs_de.set_lo(num);
 	}
 
+	#[inline]
 	fn set_reg_h(&mut self,num: u8){
 		self.regs_hl.set_hi(num);
 	}
 
+	#[inline]
 	fn set_reg_l(&mut self,num: u8){
 		self.regs_hl.set_lo(num);
 	}
 
 	//gets
+	#[inline]
 	fn get_reg_a(&mut self)->u8{
 		self.regs_af.get_hi()
 	}
 
+	#[inline]
 	fn get_reg_b(&mut self)->u8{
 		self.regs_bc.get_hi()
 	}
 
-	fn get_reg_c(&mut self)->u8{
-		self.regs_bc.get_lo()
-	}
-
+	#[inline]
 	fn get_reg_d(&mut self)->u8{
 		self.regs_de.get_hi()
 	}
 
-	fn get_reg_e(&mut self)->u8{
-		self.regs_de.get_lo()
-	}
-
-	fn get_reg_h(&mut self)->u8{
-		self.regs_hl.get_hi()
-	}
-
-	fn get_reg_l(&mut self)->u8{
-		self.regs_hl.get_lo()
-	}
-
 	//initial state taken from codeslinger (as did almost everything that sounds tricky)
 	pub fn new() -> Self{
+		let regs_af=Register::new(0x01B0);
+		//F lives in flags, the low byte of regs_af is never read
+		let flags=Flags::from_byte(regs_af.get_lo());
 		Cpu{
 			reg_pc: 0x0100,
 			reg_sp: Register::new(0xFFFE),
-			regs_af: Register::new(0x01B0),
+			regs_af,
 			regs_bc: Register::new(0x0013),
 			regs_de: Register::new(0x00D8),
 			regs_hl: Register::new(0x014D),
-			flags: Flags{z:false,n:false,
-							h:false,c:false}
+			flags,
+			verbose: true,
+			hooks: Hooks::default(),
+		}
+	}
+
+	//back to the power on state, keeping hooks and verbosity.
+	pub fn reset(&mut self){
+		let hooks=::std::mem::take(&mut self.hooks);
+		let verbose=self.verbose;
+		*self=Cpu::new();
+		self.hooks=hooks;
+		self.verbose=verbose;
+	}
+
+	pub fn set_verbose(&mut self,verbose: bool){
+		self.verbose=verbose;
+	}
+
+	pub fn state(&self)->CpuState{
+		CpuState{
+			a: self.regs_af.get_hi(),
+			f: self.flags.to_byte(),
+			b: self.regs_bc.get_hi(),
+			c: self.regs_bc.get_lo(),
+			d: self.regs_de.get_hi(),
+			e: self.regs_de.get_lo(),
+			h: self.regs_hl.get_hi(),
+			l: self.regs_hl.get_lo(),
+			sp: self.reg_sp.get(),
+			pc: self.reg_pc,
 		}
 	}
 
+	//loads registers and flags back from a state.
+	pub fn set_state(&mut self,state: CpuState){
+		self.set_reg_a(state.a);
+		self.set_reg_b(state.b);
+		self.set_reg_c(state.c);
+		self.set_reg_d(state.d);
+		self.set_reg_e(state.e);
+		self.set_reg_h(state.h);
+		self.set_reg_l(state.l);
+		self.flags=Flags::from_byte(state.f);
+		self.reg_sp.set(state.sp);
+		self.reg_pc=state.pc;
+	}
+
+	//calls `hook` every time pc reaches `pc`, before the instruction runs.
+	pub fn set_hook(&mut self,pc: u16,hook: Hook){
+		self.hooks.set(pc,hook);
+	}
+
+	pub fn remove_hook(&mut self,pc: u16){
+		self.hooks.remove(pc);
+	}
+
+	pub fn has_hook(&self,pc: u16)->bool{
+		self.hooks.contains(pc)
+	}
+
 	pub fn execute_next_opcode(&mut self,inter:&mut Interconnect)->usize{
-		let op = inter.read(self.reg_pc);
+		if self.hooks.contains(self.reg_pc) {
+			let mut state=self.state();
+			let action=self.hooks.call(&mut state,inter);
+			self.set_state(state);
+			if let HookAction::Skip(cycles)=action {
+				return cycles;
+			}
+		}
+		let op = inter.fetch(self.reg_pc);
 		//wrapping add to prevent overflow
 		self.reg_pc=self.reg_pc.wrapping_add(1);
 		self.execute_opcode(inter,op)
 	}
 
-	//TODO move instructions to separate functions? Will it look better?
 	fn execute_opcode(&mut self,inter:&mut Interconnect,opcode: u8) -> usize{
-		//unwraps opcode and panics if none
-		let value=Opcode::from_u8(opcode).unwrap_or_else(||
-            panic!("Unrecognized Opcode: {:#X})",opcode)
-        );
-        println!("Currently running opcode {:#X} from address {:#X}",opcode,self.reg_pc-1);
-        //TODO: Look for a way to remove the Opcode::opcode
-		match value{
-			//0x00
-			Opcode::Nop     => 4,
-			//0x0B
-			Opcode::Dec_Bc  => {
-				////////////////////////////// !!!!!Unwrapped sub???! 
-				let num=self.regs_bc.get().wrapping_sub(1);
-				self.regs_bc.set(num);
-				8
-			},
-			//TODO:0x25
-			Opcode::Dec_H   => {
-				4
-			},
-			//0x2F
-			Opcode::Cpl     => {
-				let num=self.get_reg_a();
-				self.set_reg_a(!num);
-				self.set_subtract_flag(true);
-				self.set_half_carry_flag(true);
-				4
-			},
-			//0xC3
-			Opcode::Jp_a16  => {
-				self.reg_pc=inter.read_16bits(self.reg_pc);
-				16
-			},
-			//0x48
-			Opcode::Ld_C_B  => {
-				let num=self.get_reg_b();
-				self.set_reg_c(num);
-				4
-			},
-			//0x49 Is it there for completeness?
-			Opcode::Ld_C_C  => 4,
-			//0x4A
-			Opcode::Ld_C_D  => {
-				let num=self.get_reg_d();
-				self.set_reg_c(num);
-				4
-			}
-			_ => 0,
+		if self.verbose {
+			println!("Currently running opcode {:#X} from address {:#X}",opcode,self.reg_pc-1);
+		}
+		INSTRUCTIONS[opcode as usize](self,inter)
+	}
+
+	//0x00
+	fn nop(&mut self,_inter:&mut Interconnect)->usize{
+		4
+	}
+
+	//0x0B
+	fn dec_bc(&mut self,_inter:&mut Interconnect)->usize{
+		let num=self.regs_bc.get().wrapping_sub(1);
+		self.regs_bc.set(num);
+		8
+	}
+
+	//TODO:0x25
+	fn dec_h(&mut self,_inter:&mut Interconnect)->usize{
+		4
+	}
+
+	//0x2F
+	fn cpl(&mut self,_inter:&mut Interconnect)->usize{
+		let num=self.get_reg_a();
+		self.set_reg_a(!num);
+		self.set_subtract_flag(true);
+		self.set_half_carry_flag(true);
+		4
+	}
+
+	//0xC3
+	fn jp_a16(&mut self,inter:&mut Interconnect)->usize{
+		self.reg_pc=inter.read_16bits(self.reg_pc);
+		16
+	}
+
+	//0x48
+	fn ld_c_b(&mut self,_inter:&mut Interconnect)->usize{
+		let num=self.get_reg_b();
+		self.set_reg_c(num);
+		4
+	}
+
+	//0x49 Is it there for completeness?
+	fn ld_c_c(&mut self,_inter:&mut Interconnect)->usize{
+		4
+	}
+
+	//0x4A
+	fn ld_c_d(&mut self,_inter:&mut Interconnect)->usize{
+		let num=self.get_reg_d();
+		self.set_reg_c(num);
+		4
+	}
+
+	//decoded but not emulated yet.
+	fn unimplemented(&mut self,_inter:&mut Interconnect)->usize{
+		0
+	}
+
+	fn unknown(&mut self,inter:&mut Interconnect)->usize{
+		let opcode=inter.peek(self.reg_pc.wrapping_sub(1));
+		panic!("Unrecognized Opcode: {:#X})",opcode)
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{Cpu,CpuState,Flags};
+
+	#[test]
+	fn flags_byte_round_trip(){
+		for f in 0..=255u8 {
+			assert_eq!(Flags::from_byte(f).to_byte(),f&0xF0);
 		}
+		assert_eq!(Flags::from_byte(0x80),Flags{z:true,n:false,h:false,c:false});
+	}
+
+	#[test]
+	fn dmg_power_on_state(){
+		let state=Cpu::new().state();
+		assert_eq!(state,CpuState{a:0x01,f:0xB0,b:0x00,c:0x13,d:0x00,e:0xD8,h:0x01,l:0x4D,sp:0xFFFE,pc:0x0100});
 	}
 
-	//fn dec
-	/*fn get_reg_l(&mut self)->u8{
-		self.regs_hl.get_lo()
-	}*/
+	#[test]
+	fn f_low_nibble_stays_zero(){
+		let mut cpu=Cpu::new();
+		let mut state=cpu.state();
+		state.f=0xFF;
+		cpu.set_state(state);
+		assert_eq!(cpu.state().f,0xF0);
+	}
 }
@@ -0,0 +1,115 @@
+use gb::gameboy::{GameBoy,EmuEvent};
+use gb::joypad::JoypadState;
+
+//Everything the emulation loop needs from whatever is showing the game.
+pub trait Frontend{
+	//called once per frame, as VBlank starts.
+	fn present_frame(&mut self,gb: &GameBoy);
+	//called once per frame as VBlank starts, returns every key held.
+	fn poll_input(&mut self)->JoypadState;
+	//nothing produces samples until the APU mixes them.
+	fn push_audio(&mut self,samples: &[i16]);
+	fn should_quit(&self)->bool;
+	//rumble and the like, ignored unless the frontend can do something
+	//with them.
+	fn handle_event(&mut self,_event: EmuEvent){}
+}
+
+//A frontend wrapping another one to add to what it does, like pacing or
+//watching the rom. Whatever it doesn't override goes to the inner one.
+pub trait Wrapper{
+	fn inner(&self)->&dyn Frontend;
+	fn inner_mut(&mut self)->&mut dyn Frontend;
+
+	fn present_frame(&mut self,gb: &GameBoy){
+		self.inner_mut().present_frame(gb);
+	}
+
+	fn poll_input(&mut self)->JoypadState{
+		self.inner_mut().poll_input()
+	}
+
+	fn push_audio(&mut self,samples: &[i16]){
+		self.inner_mut().push_audio(samples);
+	}
+
+	fn should_quit(&self)->bool{
+		self.inner().should_quit()
+	}
+
+	fn handle_event(&mut self,event: EmuEvent){
+		self.inner_mut().handle_event(event);
+	}
+}
+
+impl<W: Wrapper> Frontend for W{
+	fn present_frame(&mut self,gb: &GameBoy){
+		Wrapper::present_frame(self,gb);
+	}
+
+	fn poll_input(&mut self)->JoypadState{
+		Wrapper::poll_input(self)
+	}
+
+	fn push_audio(&mut self,samples: &[i16]){
+		Wrapper::push_audio(self,samples);
+	}
+
+	fn should_quit(&self)->bool{
+		Wrapper::should_quit(self)
+	}
+
+	fn handle_event(&mut self,event: EmuEvent){
+		Wrapper::handle_event(self,event);
+	}
+}
+
+//Dumps the machine state at the end of every frame, with the keys held
+//when there are any.
+pub struct Console;
+
+impl Frontend for Console{
+	fn present_frame(&mut self,gb: &GameBoy){
+		println!("{}",gb);
+		let keys=gb.interconnect().joypad().state();
+		if keys!=JoypadState::default() {
+			println!("Input: {}",keys);
+		}
+	}
+
+	fn poll_input(&mut self)->JoypadState{
+		JoypadState::default()
+	}
+
+	fn push_audio(&mut self,_samples: &[i16]){}
+
+	fn should_quit(&self)->bool{
+		false
+	}
+}
+
+//Runs without any output, useful for test ROMs and benchmarking.
+pub struct Headless;
+
+impl Frontend for Headless{
+	fn present_frame(&mut self,_gb: &GameBoy){}
+
+	fn poll_input(&mut self)->JoypadState{
+		JoypadState::default()
+	}
+
+	fn push_audio(&mut self,_samples: &[i16]){}
+
+	fn should_quit(&self)->bool{
+		false
+	}
+}
+
+//picks a frontend by its command line name.
+pub fn from_name(name: &str)->Option<Box<dyn Frontend>>{
+	match name{
+		"console"  => Some(Box::new(Console)),
+		"headless" => Some(Box::new(Headless)),
+		_          => None,
+	}
+}
@@ -1,18 +1,260 @@
 use gb::cpu::*;
 use gb::interconnect::*;
+use gb::frontend::Frontend;
+use gb::trace::{TraceComparer,Outcome};
+use gb::serial::SerialDevice;
+use gb::hooks::Hook;
+use gb::timing::TimingCheck;
+use gb::sram_watch::SramWatch;
+use gb::checksum::Fnv;
+use gb::regwatch::RegisterWatch;
+use gb::memdiff::{self,MemoryDiff};
+use gb::history::RegisterHistory;
+use gb::breakpoints::Breakpoint;
+use gb::joypad::JoypadState;
+use gb::snapshot::{Snapshot,SnapshotReader};
+use std::ops::RangeInclusive;
+use std::io::Write;
 use std::fmt::{Display,Result,Formatter};
 
+//a DMG frame lasts 154 scanlines of 456 cycles.
+pub const CYCLES_PER_FRAME: u64 = 70224;
+//VBlank starts after the 144 visible lines.
+pub const VBLANK_START: u64 = 144*456;
+
+//things happening on the emulated hardware frontends may want to forward.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum EmuEvent{
+	//MBC5 rumble intensity from 0 (off) to 1, sent once per frame when it
+	//changes.
+	Rumble(f32),
+}
+
+//what came out of the machine during one run_frame.
+#[derive(Debug,Default)]
+pub struct FrameOutput{
+	//frame that just finished.
+	pub frame: u64,
+	pub events: Vec<EmuEvent>,
+	//samples for Frontend::push_audio, empty until the APU mixes them.
+	pub audio: Vec<i16>,
+	//bytes sent over the link port.
+	pub serial: Vec<u8>,
+	//cartridge ram was written, the save should be flushed.
+	pub save_dirty: bool,
+	//a breakpoint, watch or the reference trace stopped the frame before
+	//it ended.
+	pub stopped: bool,
+}
+
 pub struct GameBoy{
 	cpu: Cpu,
 	interconnect: Interconnect,
+	trace: Option<TraceComparer>,
+	//cycles run since power on, at the normal clock. Everything but the cpu
+	//runs on these.
+	cycles: u64,
+	//overclocking, the cpu runs this many cycles for each normal one.
+	cpu_speed: u32,
+	//cpu cycles not yet converted into normal ones.
+	cpu_remainder: u32,
+	break_at: Option<u64>,
+	//break_at hasn't fired since it was set or the cartridge was loaded.
+	break_armed: bool,
+	//ranges captured in the per frame snapshots, and where they go.
+	snapshot_ranges: Vec<RangeInclusive<u16>>,
+	snapshots: Option<SnapshotReader>,
+	//events not yet taken by the frontend.
+	events: Vec<EmuEvent>,
+	timing: Option<TimingCheck>,
+	sram_watch: Option<SramWatch>,
+	register_watches: Vec<RegisterWatch>,
+	//why the run has to stop, set by watches during a step.
+	stop_reason: Option<String>,
+	//where per frame state checksums are logged.
+	checksum_log: Option<Box<dyn Write>>,
+	memory_diffs: Vec<MemoryDiff>,
+	history: Option<RegisterHistory>,
+	breakpoints: Vec<Breakpoint>,
 }
 
 impl GameBoy{
 
-	pub fn new(cart:Vec<u8>)->Self{
-		GameBoy{cpu: Cpu::new(),interconnect: Interconnect::new(cart)}
+	pub fn new(cart:Vec<u8>,ram_init: RamInit)->Self{
+		GameBoy{
+			cpu: Cpu::new(),
+			interconnect: Interconnect::new(cart,ram_init),
+			trace: None,
+			cycles: 0,
+			cpu_speed: 1,
+			cpu_remainder: 0,
+			break_at: None,
+			break_armed: false,
+			snapshot_ranges: Vec::new(),
+			snapshots: None,
+			events: Vec::new(),
+			timing: None,
+			sram_watch: None,
+			register_watches: Vec::new(),
+			stop_reason: None,
+			checksum_log: None,
+			memory_diffs: Vec::new(),
+			history: None,
+			breakpoints: Vec::new(),
+		}
 	}
 
+    //swaps the cartridge and power cycles. Breakpoints, watches, hooks and
+    //whatever is plugged into the link port are kept.
+    pub fn load_cartridge(&mut self,cart:Vec<u8>){
+        self.cpu.reset();
+        self.interconnect.load_cartridge(cart);
+        self.cycles=0;
+        self.cpu_remainder=0;
+        self.stop_reason=None;
+        self.break_armed=self.break_at.is_some();
+        self.events.clear();
+    }
+
+    pub fn connect_serial(&mut self,device: Box<dyn SerialDevice>){
+        self.interconnect.connect_serial(device);
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.cycles/CYCLES_PER_FRAME
+    }
+
+    //runs the cpu `speed` times faster than the rest of the hardware, so
+    //games that lag get more instructions per frame.
+    pub fn set_cpu_speed(&mut self,speed: u32){
+        assert!(speed>0,"cpu speed must be at least 1");
+        self.cpu_speed=speed;
+    }
+
+    //stops the run before the first instruction starting at or after
+    //`cycle`, the earliest breakpoint wins if called more than once.
+    pub fn break_at_cycle(&mut self,cycle: u64){
+        self.break_at=Some(self.break_at.map_or(cycle,|c| c.min(cycle)));
+        self.break_armed=true;
+    }
+
+    pub fn break_at_frame(&mut self,frame: u64){
+        self.break_at_cycle(frame*CYCLES_PER_FRAME);
+    }
+
+    //checks every instruction against a reference log, stopping at the
+    //first divergence.
+    pub fn compare_trace(&mut self,trace: TraceComparer){
+        self.trace=Some(trace);
+    }
+
+    //prints writes to cartridge ram with the instruction doing them, and
+    //stops at the first one with SramWatch::Break.
+    pub fn watch_sram(&mut self,watch: SramWatch){
+        self.sram_watch=Some(watch);
+        self.interconnect.watch_sram();
+    }
+
+    //hash of the registers, WRAM, VRAM, OAM and HRAM. Two runs that agree on
+    //it are very likely in the same state.
+    pub fn state_checksum(&self) -> u64 {
+        let state=self.cpu.state();
+        let inter=&self.interconnect;
+        let mut fnv=Fnv::default();
+        fnv.write(&[state.a,state.f,state.b,state.c,state.d,state.e,state.h,state.l]);
+        fnv.write(&[(state.sp>>8) as u8,state.sp as u8,(state.pc>>8) as u8,state.pc as u8]);
+        fnv.write(inter.wram());
+        fnv.write(inter.vram());
+        fnv.write(inter.oam());
+        fnv.write(inter.hram());
+        fnv.finish()
+    }
+
+    //logs "<frame> <cycles> <checksum>" at the end of every frame, so the
+    //logs of two runs can be diffed to find the first frame they disagree.
+    pub fn log_checksums(&mut self,log: Box<dyn Write>){
+        self.checksum_log=Some(log);
+    }
+
+    //stops the run when pc reaches breakpoint.pc, after running its
+    //actions. Replaces any other breakpoint at the same address.
+    pub fn break_at_pc(&mut self,breakpoint: Breakpoint){
+        self.breakpoints.retain(|b| b.pc!=breakpoint.pc);
+        self.breakpoints.push(breakpoint);
+    }
+
+    //stops the run when a register or flag changes, see RegisterWatch.
+    pub fn watch_register(&mut self,watch: RegisterWatch){
+        self.register_watches.push(watch);
+    }
+
+    //prints the bytes of start..=end that changed at the end of every frame.
+    pub fn diff_memory(&mut self,start: u16,end: u16){
+        let inter=&self.interconnect;
+        self.memory_diffs.push(MemoryDiff::new(start,end,&|a| inter.peek(a)));
+    }
+
+    //keeps the registers of the last `steps` instructions, see
+    //RegisterHistory.
+    pub fn record_history(&mut self,steps: usize){
+        self.history=Some(RegisterHistory::new(steps));
+    }
+
+    pub fn history(&self) -> Option<&RegisterHistory> {
+        self.history.as_ref()
+    }
+
+    //checks the cycles of every instruction against the opcode table.
+    pub fn check_timing(&mut self){
+        self.timing=Some(TimingCheck::new());
+    }
+
+    pub fn timing(&self) -> Option<&TimingCheck> {
+        self.timing.as_ref()
+    }
+
+    //copies the registers, IO and the given memory ranges.
+    pub fn snapshot(&self,ranges: &[RangeInclusive<u16>]) -> Snapshot {
+        let inter=&self.interconnect;
+        Snapshot{
+            cycles: self.cycles,
+            frame: self.frame(),
+            cpu: self.cpu.state(),
+            io: (0xFF00..=0xFF7F).map(|a| inter.peek(a)).collect(),
+            ie: inter.peek(0xFFFF),
+            memory: ranges.iter()
+                .map(|r| (r.clone(),r.clone().map(|a| inter.peek(a)).collect()))
+                .collect(),
+        }
+    }
+
+    //publishes a snapshot with `ranges` at the end of every frame, readers
+    //pick them up through the returned handle.
+    pub fn publish_snapshots(&mut self,ranges: Vec<RangeInclusive<u16>>) -> SnapshotReader {
+        let reader=SnapshotReader::default();
+        self.snapshot_ranges=ranges;
+        self.snapshots=Some(reader.clone());
+        reader
+    }
+
+    //see Cpu::set_hook.
+    pub fn set_hook(&mut self,pc: u16,hook: Hook){
+        self.cpu.set_hook(pc,hook);
+    }
+
+    pub fn remove_hook(&mut self,pc: u16){
+        self.cpu.remove_hook(pc);
+    }
+
+    //prints every opcode as it runs, on by default.
+    pub fn set_verbose(&mut self,verbose: bool){
+        self.cpu.set_verbose(verbose);
+    }
+
     pub fn cpu(&self) -> &Cpu {
         &self.cpu
     }
@@ -21,10 +263,217 @@ impl GameBoy{
         &self.interconnect
     }
 
-    pub fn run(&mut self){
-        loop{
-            self.cpu.execute_next_opcode(&mut self.interconnect);
+    pub fn interconnect_mut(&mut self) -> &mut Interconnect {
+        &mut self.interconnect
+    }
+
+    //events since the last call, oldest first.
+    pub fn take_events(&mut self) -> Vec<EmuEvent> {
+        ::std::mem::take(&mut self.events)
+    }
+
+    //replaces every key held at once, games see it from the next P1 read.
+    pub fn set_joypad_state(&mut self,state: JoypadState){
+        self.interconnect.set_joypad_state(state);
+    }
+
+    //press or release single keys on top of the current state, for
+    //debuggers. Frontends hand over the whole state from poll_input.
+    pub fn press(&mut self,keys: JoypadState){
+        self.interconnect.joypad_mut().press(keys);
+    }
+
+    pub fn release(&mut self,keys: JoypadState){
+        self.interconnect.joypad_mut().release(keys);
+    }
+
+    //VBlanks started so far, counting power on as one.
+    fn vblanks(&self) -> u64 {
+        (self.cycles+CYCLES_PER_FRAME-VBLANK_START)/CYCLES_PER_FRAME
+    }
+
+    //runs frame after frame until the frontend quits or something stops
+    //the run. Input is polled right as VBlank starts, where games read the
+    //joypad, so it is as fresh as it can be.
+    pub fn run(&mut self,frontend: &mut dyn Frontend){
+        while !frontend.should_quit(){
+            let keys=frontend.poll_input();
+            let output=self.run_frame(keys);
+            for event in output.events{
+                frontend.handle_event(event);
+            }
+            if output.stopped {
+                break;
+            }
+            frontend.push_audio(&output.audio);
+            frontend.present_frame(self);
+        }
+    }
+
+    //runs instructions until the next frame starts.
+    pub fn step_frame(&mut self){
+        let frame=self.frame();
+        while self.frame()==frame {
+            self.step();
+        }
+    }
+
+    //runs with `input` held until the next VBlank starts, when the
+    //picture is complete, and hands over everything the frame produced.
+    //Frontends get driven by run, this is for embedding the core without
+    //one.
+    pub fn run_frame(&mut self,input: JoypadState) -> FrameOutput {
+        self.set_joypad_state(input);
+        let vblank=self.vblanks();
+        let mut stopped=false;
+        while self.vblanks()==vblank {
+            if !self.check_trace() || self.check_breakpoint() {
+                stopped=true;
+                break;
+            }
+            self.step();
+        }
+        FrameOutput{
+            frame: self.frame(),
+            events: self.take_events(),
+            audio: Vec::new(),
+            serial: self.interconnect.take_serial_sent(),
+            save_dirty: self.interconnect.cartridge_mut().take_ram_dirty(),
+            stopped,
+        }
+    }
+
+    //runs a single instruction.
+    pub fn step(&mut self){
+        let frame=self.frame();
+        let before=self.cpu.state();
+        let pc=before.pc;
+        if let Some(ref mut history)=self.history {
+            history.record(self.cycles,before);
+        }
+        //hooks can skip the instruction, so their cycles say nothing
+        let opcode=match self.timing{
+            Some(_) if !self.cpu.has_hook(pc) => Some(self.interconnect.peek(pc)),
+            _                                 => None,
+        };
+        let cycles=self.cpu.execute_next_opcode(&mut self.interconnect);
+        if let (Some(timing),Some(opcode))=(self.timing.as_mut(),opcode) {
+            timing.record(pc,opcode,cycles);
+        }
+        let cpu_cycles=cycles as u32+self.cpu_remainder;
+        let cycles=cpu_cycles/self.cpu_speed;
+        self.cpu_remainder=cpu_cycles%self.cpu_speed;
+        self.interconnect.step(cycles);
+        self.cycles+=cycles as u64;
+        if let Some(watch)=self.sram_watch {
+            for write in self.interconnect.take_sram_writes(){
+                println!("{}",write);
+                if watch==SramWatch::Break && self.stop_reason.is_none() {
+                    self.stop_reason=Some(String::from("a cartridge ram write"));
+                }
+            }
+        }
+        if !self.register_watches.is_empty() {
+            self.check_register_watches(&before);
+        }
+        if !self.breakpoints.is_empty() {
+            self.check_pc_breakpoints();
+        }
+        if self.frame()!=frame {
+            self.end_frame();
+        }
+    }
+
+    fn end_frame(&mut self){
+        if self.checksum_log.is_some() {
+            let line=format!("{} {} {:016x}\n",self.frame(),self.cycles,self.state_checksum());
+            let log=self.checksum_log.as_mut().unwrap();
+            if let Err(e)=log.write_all(line.as_bytes()) {
+                eprintln!("Cannot write checksum log: {}",e);
+                self.checksum_log=None;
+            }
+        }
+        let frame=self.frame();
+        let inter=&self.interconnect;
+        for diff in &mut self.memory_diffs{
+            let changes=diff.update(&|a| inter.peek(a));
+            if !changes.is_empty() {
+                println!("frame {}: {}",frame,memdiff::format_changes(&changes));
+            }
+        }
+        if let Some(intensity)=self.interconnect.cartridge_mut().end_frame() {
+            self.events.push(EmuEvent::Rumble(intensity));
+        }
+        if let Some(ref reader)=self.snapshots {
+            reader.publish(self.snapshot(&self.snapshot_ranges));
+        }
+    }
+
+    fn check_register_watches(&mut self,before: &CpuState){
+        let after=self.cpu.state();
+        for watch in &self.register_watches{
+            if let Some((old,new))=watch.check(before,&after) {
+                self.stop_reason=Some(format!("{} changing from {:04X} to {:04X} at PC {:04X}",
+                    watch.name(),old,new,before.pc));
+                return;
+            }
+        }
+    }
+
+    //breakpoints are checked once the previous instruction has run, so the
+    //run stops before the one at the breakpoint.
+    fn check_pc_breakpoints(&mut self){
+        let state=self.cpu.state();
+        let breakpoint=match self.breakpoints.iter().find(|b| b.pc==state.pc){
+            Some(breakpoint) => breakpoint,
+            None             => return,
+        };
+        let inter=&self.interconnect;
+        let peek=|a| if inter.is_mapped(a) {Some(inter.peek(a))} else {None};
+        for line in breakpoint.run_actions(&state,&peek){
+            println!("{}",line);
+        }
+        if !breakpoint.continues() && self.stop_reason.is_none() {
+            self.stop_reason=Some(format!("the breakpoint at {:04X}",state.pc));
+        }
+    }
+
+    //reports a stop once, the next run carries on from there.
+    fn check_breakpoint(&mut self)->bool{
+        if let Some(reason)=self.stop_reason.take() {
+            println!("Stopped on {} at cycle {}",reason,self.cycles());
             println!("{}",self);
+            return true;
+        }
+        match self.break_at{
+            Some(cycle) if self.break_armed && self.cycles>=cycle => {
+                self.break_armed=false;
+                println!("Stopped at cycle {} (frame {})",self.cycles(),self.frame());
+                println!("{}",self);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn check_trace(&mut self)->bool{
+        let trace=match self.trace{
+            Some(ref mut trace) => trace,
+            None                => return true,
+        };
+        match trace.check(self.cpu.state()){
+            Outcome::Match => true,
+            Outcome::Ended(lines) => {
+                println!("Reference trace ended after {} lines without diverging",lines);
+                false
+            },
+            Outcome::Diverged{line,expected,actual} => {
+                println!("Diverged from reference trace at line {}",line);
+                println!("expected: {}",expected);
+                println!("actual:   {}",actual);
+                println!("{}",self);
+                false
+            },
         }
     }
 
@@ -36,4 +485,219 @@ impl Display for GameBoy {
     fn fmt(&self, f: &mut Formatter) -> Result {
     	write!(f,"{:#?}",self.cpu)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests{
+
+    use super::GameBoy;
+    use gb::interconnect::RamInit;
+    use gb::hooks::HookAction;
+    use gb::testrom;
+    use gb::regwatch::RegisterWatch;
+    use gb::frontend::{Frontend,Headless};
+    use gb::joypad::JoypadState;
+    use gb::breakpoints::Breakpoint;
+    use gb::cartridge::RAW_LOAD_ADDRESS;
+    use std::rc::Rc;
+    use std::cell::Cell;
+
+    //32KB of nops, MBC0.
+    fn nop_rom()->Vec<u8>{
+        vec![0;0x8000]
+    }
+
+    #[test]
+    fn snapshot_published_every_frame(){
+        let mut gb=GameBoy::new(nop_rom(),RamInit::Ones);
+        let reader=gb.publish_snapshots(vec![0xC000..=0xC00F]);
+        while gb.frame()==0 {
+            assert!(reader.latest().is_none());
+            gb.step();
+        }
+        let snapshot=reader.latest().unwrap();
+        assert_eq!(snapshot.frame,1);
+        assert_eq!(snapshot.cpu.pc,gb.cpu().state().pc);
+        assert_eq!(snapshot.read(0xC00F),Some(0xFF));
+        assert_eq!(snapshot.read(0xC010),None);
+    }
+
+    #[test]
+    fn hooks_run_before_the_instruction(){
+        let mut gb=GameBoy::new(nop_rom(),RamInit::Zero);
+        gb.set_verbose(false);
+        let calls=Rc::new(Cell::new(0));
+        let counter=calls.clone();
+        gb.set_hook(0x100,Box::new(move |_,_| {
+            counter.set(counter.get()+1);
+            HookAction::Execute
+        }));
+        //pretend 0x101 is a routine returning 0x42 in A
+        gb.set_hook(0x101,Box::new(|state,_| {
+            state.a=0x42;
+            state.pc=0x200;
+            HookAction::Skip(24)
+        }));
+        gb.step();
+        gb.step();
+        assert_eq!(calls.get(),1);
+        assert_eq!(gb.cycles(),4+24);
+        assert_eq!(gb.cpu().state().a,0x42);
+        assert_eq!(gb.cpu().state().pc,0x200);
+        gb.remove_hook(0x100);
+        gb.step();
+        assert_eq!(gb.cpu().state().pc,0x201);
+    }
+
+    #[test]
+    fn overclocked_cpu_runs_more_instructions_per_cycle(){
+        let mut gb=GameBoy::new(nop_rom(),RamInit::Zero);
+        gb.set_verbose(false);
+        gb.set_cpu_speed(3);
+        for _ in 0..4 {
+            gb.step();
+        }
+        //16 cpu cycles, the leftover one is carried over
+        assert_eq!(gb.cycles(),5);
+        gb.step();
+        assert_eq!(gb.cycles(),6);
+    }
+
+    #[test]
+    fn run_frame_reports_save_writes(){
+        //the header would run as code in a nop rom
+        let mut rom=testrom::tiny_rom();
+        rom[0x147]=0x03;
+        rom[0x149]=0x02;
+        let mut gb=GameBoy::new(rom,RamInit::Zero);
+        gb.set_verbose(false);
+        let output=gb.run_frame(JoypadState::A);
+        assert_eq!(output.frame,0);
+        assert_eq!(gb.interconnect().joypad().state(),JoypadState::A);
+        assert!(!output.save_dirty);
+        gb.interconnect_mut().write(0xA000,0x01);
+        let output=gb.run_frame(JoypadState::default());
+        assert_eq!(output.frame,1);
+        assert!(output.save_dirty);
+        assert!(output.serial.is_empty());
+    }
+
+    #[test]
+    fn checksum_follows_state(){
+        let mut a=GameBoy::new(nop_rom(),RamInit::Zero);
+        let b=GameBoy::new(nop_rom(),RamInit::Zero);
+        assert_eq!(a.state_checksum(),b.state_checksum());
+        a.interconnect_mut().write(0xD000,0x01);
+        assert!(a.state_checksum()!=b.state_checksum());
+    }
+
+    #[test]
+    fn register_watch_stops_the_run(){
+        let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+        gb.set_verbose(false);
+        gb.watch_register(RegisterWatch::parse("PC==150").unwrap());
+        gb.run(&mut Headless);
+        //nop at 0x100, then jp 0x150
+        assert_eq!(gb.cpu().state().pc,0x150);
+        assert_eq!(gb.cycles(),4+16);
+    }
+
+    #[test]
+    fn load_cartridge_keeps_breakpoints(){
+        let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+        gb.set_verbose(false);
+        gb.break_at_cycle(100);
+        gb.run(&mut Headless);
+        gb.interconnect_mut().write(0xC000,0x12);
+        let mut rom=testrom::tiny_rom();
+        rom[0x134]=b'X';
+        gb.load_cartridge(rom);
+        assert_eq!(gb.cycles(),0);
+        assert_eq!(gb.cpu().state().pc,0x100);
+        assert_eq!(gb.interconnect().read(0xC000),0x00);
+        assert_eq!(gb.interconnect().cartridge().title(),"XAW");
+        gb.run(&mut Headless);
+        assert!(gb.cycles()>=100 && gb.cycles()<120);
+    }
+
+    //holds A and counts how often it was asked.
+    struct Polls(u32);
+
+    impl Frontend for Polls{
+        fn present_frame(&mut self,_gb: &GameBoy){}
+
+        fn poll_input(&mut self) -> JoypadState {
+            self.0+=1;
+            JoypadState::A
+        }
+
+        fn push_audio(&mut self,_samples: &[i16]){}
+
+        fn should_quit(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn input_polled_once_per_vblank(){
+        let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+        gb.set_verbose(false);
+        gb.break_at_frame(2);
+        let mut frontend=Polls(0);
+        gb.run(&mut frontend);
+        //power on, then the VBlanks of frames 0 and 1
+        assert_eq!(frontend.0,3);
+        gb.interconnect_mut().write(0xFF00,0x10);
+        assert_eq!(gb.interconnect().read(0xFF00),0xDE);
+    }
+
+    #[test]
+    fn runs_on_after_a_stop(){
+        let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+        gb.set_verbose(false);
+        //the jp of TINY_PROGRAM
+        gb.break_at_pc(Breakpoint::new(RAW_LOAD_ADDRESS+1));
+        gb.run(&mut Headless);
+        assert_eq!(gb.cpu().state().pc,RAW_LOAD_ADDRESS+1);
+        //steps past it and around the loop to it again
+        let cycles=gb.cycles();
+        gb.run(&mut Headless);
+        assert!(gb.cycles()>cycles);
+        assert_eq!(gb.cpu().state().pc,RAW_LOAD_ADDRESS+1);
+        //a cycle break fires once
+        gb.break_at_cycle(0);
+        gb.run(&mut Headless);
+        let cycles=gb.cycles();
+        gb.run(&mut Headless);
+        assert!(gb.cycles()>cycles);
+    }
+
+    //counts the frames it was shown.
+    struct Presents(u32);
+
+    impl Frontend for Presents{
+        fn present_frame(&mut self,_gb: &GameBoy){
+            self.0+=1;
+        }
+
+        fn poll_input(&mut self) -> JoypadState {
+            JoypadState::default()
+        }
+
+        fn push_audio(&mut self,_samples: &[i16]){}
+
+        fn should_quit(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn frames_presented_once_per_vblank(){
+        let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+        gb.set_verbose(false);
+        gb.break_at_frame(2);
+        let mut frontend=Presents(0);
+        gb.run(&mut frontend);
+        //the VBlanks of frames 0 and 1
+        assert_eq!(frontend.0,2);
+    }
+}
@@ -0,0 +1,97 @@
+//The registers before each of the last instructions, to see when one of
+//them picked up a bad value without running the program again.
+use gb::cpu::CpuState;
+use std::collections::VecDeque;
+
+pub struct RegisterHistory{
+	capacity: usize,
+	//(cycle, registers) oldest first.
+	entries: VecDeque<(u64,CpuState)>,
+}
+
+impl RegisterHistory{
+	pub fn new(capacity: usize)->Self{
+		RegisterHistory{capacity,entries: VecDeque::with_capacity(capacity)}
+	}
+
+	//`state` is taken before the instruction at state.pc runs.
+	pub fn record(&mut self,cycle: u64,state: CpuState){
+		if self.capacity==0 {
+			return;
+		}
+		if self.entries.len()==self.capacity {
+			self.entries.pop_front();
+		}
+		self.entries.push_back((cycle,state));
+	}
+
+	pub fn entries(&self)->impl Iterator<Item=&(u64,CpuState)>{
+		self.entries.iter()
+	}
+
+	//one line a step, oldest first, ending with the registers that step
+	//changed so the bad value stands out.
+	pub fn format(&self)->Vec<String>{
+		let mut lines=Vec::new();
+		let mut steps=self.entries.iter().peekable();
+		while let Some(&(cycle,state))=steps.next() {
+			let mut line=format!("{:>10} {}",cycle,state);
+			if let Some((_,next))=steps.peek() {
+				let changes=changed_registers(&state,next);
+				if !changes.is_empty() {
+					line.push_str(&format!(" |{}",changes));
+				}
+			}
+			lines.push(line);
+		}
+		lines
+	}
+}
+
+//" A=3C SP=DFF0" for the registers that differ, leaving out pc.
+fn changed_registers(before: &CpuState,after: &CpuState)->String{
+	let mut changes=String::new();
+	let bytes=[("A",before.a,after.a),("F",before.f,after.f),("B",before.b,after.b),
+		("C",before.c,after.c),("D",before.d,after.d),("E",before.e,after.e),
+		("H",before.h,after.h),("L",before.l,after.l)];
+	for &(name,old,new) in bytes.iter(){
+		if old!=new {
+			changes.push_str(&format!(" {}={:02X}",name,new));
+		}
+	}
+	if before.sp!=after.sp {
+		changes.push_str(&format!(" SP={:04X}",after.sp));
+	}
+	changes
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::RegisterHistory;
+	use gb::cpu::CpuState;
+
+	fn state(a: u8,pc: u16)->CpuState{
+		CpuState{a,f:0,b:0,c:0,d:0,e:0,h:0,l:0,sp:0xFFFE,pc}
+	}
+
+	#[test]
+	fn keeps_the_last_steps(){
+		let mut history=RegisterHistory::new(2);
+		for pc in 0..5 {
+			history.record(pc as u64*4,state(0,pc));
+		}
+		let pcs: Vec<u16>=history.entries().map(|&(_,state)| state.pc).collect();
+		assert_eq!(pcs,vec![3,4]);
+	}
+
+	#[test]
+	fn marks_changed_registers(){
+		let mut history=RegisterHistory::new(4);
+		history.record(0,state(0,0x100));
+		history.record(8,state(0x3C,0x102));
+		let lines=history.format();
+		assert!(lines[0].ends_with("PC:0100 | A=3C"),"{}",lines[0]);
+		assert!(lines[1].ends_with("PC:0102"),"{}",lines[1]);
+	}
+}
@@ -0,0 +1,52 @@
+//Rust callbacks run when the cpu is about to execute a given address, for
+//high level emulation tricks (replacing a game's RNG routine, patching
+//around unimplemented hardware...).
+use gb::cpu::CpuState;
+use gb::interconnect::Interconnect;
+use std::collections::HashMap;
+use std::fmt;
+
+pub enum HookAction{
+	//run the instruction at pc as usual (after any change to the state).
+	Execute,
+	//don't run it and count these cycles instead. The hook is expected to
+	//move pc somewhere else, or it will be called again on the next step.
+	Skip(usize),
+}
+
+pub type Hook = Box<dyn FnMut(&mut CpuState,&mut Interconnect)->HookAction>;
+
+//one hook per address, registering another one replaces it.
+#[derive(Default)]
+pub struct Hooks{
+	table: HashMap<u16,Hook>,
+}
+
+impl Hooks{
+	pub fn set(&mut self,pc: u16,hook: Hook){
+		self.table.insert(pc,hook);
+	}
+
+	pub fn remove(&mut self,pc: u16){
+		self.table.remove(&pc);
+	}
+
+	pub fn contains(&self,pc: u16)->bool{
+		!self.table.is_empty() && self.table.contains_key(&pc)
+	}
+
+	pub fn call(&mut self,state: &mut CpuState,inter: &mut Interconnect)->HookAction{
+		match self.table.get_mut(&state.pc){
+			Some(hook) => hook(state,inter),
+			None       => HookAction::Execute,
+		}
+	}
+}
+
+impl fmt::Debug for Hooks{
+	fn fmt(&self,f: &mut fmt::Formatter)->fmt::Result{
+		let mut addresses: Vec<_>=self.table.keys().collect();
+		addresses.sort();
+		f.debug_set().entries(addresses.iter().map(|a| format!("{:#06X}",a))).finish()
+	}
+}
@@ -0,0 +1,176 @@
+//Several GameBoys running side by side, each on a thread of its own, for
+//batch runs, netplay lobbies and the like. A GameBoy isn't Send (hooks,
+//serial devices...), so every instance is built on its thread from an
+//InstanceConfig and only inputs, frames and saves cross over.
+use gb::gameboy::{GameBoy,FrameOutput};
+use gb::interconnect::RamInit;
+use gb::joypad::JoypadState;
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self,Sender,Receiver};
+use std::thread::{self,JoinHandle};
+
+pub struct InstanceConfig{
+	pub rom: Vec<u8>,
+	pub ram_init: RamInit,
+	//cartridge ram to start with.
+	pub save: Option<Vec<u8>>,
+	pub cpu_speed: u32,
+}
+
+impl InstanceConfig{
+	pub fn new(rom: Vec<u8>)->Self{
+		InstanceConfig{rom,ram_init: RamInit::Zero,save: None,cpu_speed: 1}
+	}
+}
+
+pub type InstanceId=usize;
+
+enum Command{
+	SetInput(JoypadState),
+	RunFrame,
+	Save,
+}
+
+enum Reply{
+	Frame(FrameOutput),
+	Save(Vec<u8>),
+}
+
+struct Instance{
+	commands: Sender<Command>,
+	replies: Receiver<Reply>,
+	thread: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct InstanceManager{
+	instances: BTreeMap<InstanceId,Instance>,
+	next_id: InstanceId,
+}
+
+impl InstanceManager{
+	pub fn new()->Self{
+		InstanceManager::default()
+	}
+
+	pub fn spawn(&mut self,config: InstanceConfig)->InstanceId{
+		let (commands,command_rx)=mpsc::channel();
+		let (reply_tx,replies)=mpsc::channel();
+		let thread=thread::spawn(move || serve(config,command_rx,reply_tx));
+		let id=self.next_id;
+		self.next_id+=1;
+		self.instances.insert(id,Instance{commands,replies,thread});
+		id
+	}
+
+	pub fn ids(&self)->Vec<InstanceId>{
+		self.instances.keys().cloned().collect()
+	}
+
+	//keys held from the next frame on.
+	pub fn set_input(&self,id: InstanceId,keys: JoypadState){
+		if let Some(instance)=self.instances.get(&id) {
+			let _=instance.commands.send(Command::SetInput(keys));
+		}
+	}
+
+	//None when there's no such instance or it died (panicked).
+	pub fn run_frame(&self,id: InstanceId)->Option<FrameOutput>{
+		let instance=self.instances.get(&id)?;
+		instance.commands.send(Command::RunFrame).ok()?;
+		match instance.replies.recv().ok()? {
+			Reply::Frame(output) => Some(output),
+			Reply::Save(_)       => None,
+		}
+	}
+
+	//one frame on every instance, all of them in parallel.
+	pub fn run_frame_all(&self)->Vec<(InstanceId,Option<FrameOutput>)>{
+		let started: Vec<(InstanceId,bool)>=self.instances.iter()
+			.map(|(&id,instance)| (id,instance.commands.send(Command::RunFrame).is_ok()))
+			.collect();
+		started.into_iter().map(|(id,started)| {
+			let output=match self.instances[&id].replies.recv(){
+				Ok(Reply::Frame(output)) if started => Some(output),
+				_                                   => None,
+			};
+			(id,output)
+		}).collect()
+	}
+
+	//cartridge ram as it is now.
+	pub fn save(&self,id: InstanceId)->Option<Vec<u8>>{
+		let instance=self.instances.get(&id)?;
+		instance.commands.send(Command::Save).ok()?;
+		match instance.replies.recv().ok()? {
+			Reply::Save(ram)  => Some(ram),
+			Reply::Frame(_)   => None,
+		}
+	}
+
+	//stops the instance and waits for its thread.
+	pub fn remove(&mut self,id: InstanceId){
+		if let Some(instance)=self.instances.remove(&id) {
+			drop(instance.commands);
+			let _=instance.thread.join();
+		}
+	}
+}
+
+impl Drop for InstanceManager{
+	fn drop(&mut self){
+		for id in self.ids(){
+			self.remove(id);
+		}
+	}
+}
+
+//an instance's thread, until the manager hangs up.
+fn serve(config: InstanceConfig,commands: Receiver<Command>,replies: Sender<Reply>){
+	let mut gb=GameBoy::new(config.rom,config.ram_init);
+	gb.set_verbose(false);
+	gb.set_cpu_speed(config.cpu_speed);
+	if let Some(save)=config.save {
+		gb.interconnect_mut().cartridge_mut().load_ram(&save);
+	}
+	let mut keys=JoypadState::default();
+	for command in commands{
+		let reply=match command{
+			Command::SetInput(held) => {
+				keys=held;
+				continue;
+			},
+			Command::RunFrame       => Reply::Frame(gb.run_frame(keys)),
+			Command::Save           => Reply::Save(gb.interconnect().cartridge().ram().to_vec()),
+		};
+		if replies.send(reply).is_err() {
+			return;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{InstanceConfig,InstanceManager};
+	use gb::joypad::JoypadState;
+	use gb::testrom;
+
+	#[test]
+	fn instances_run_independently(){
+		let mut manager=InstanceManager::new();
+		let a=manager.spawn(InstanceConfig::new(testrom::tiny_rom()));
+		let b=manager.spawn(InstanceConfig::new(testrom::tiny_rom()));
+		assert_eq!(manager.run_frame(a).map(|o| o.frame),Some(0));
+		manager.set_input(b,JoypadState::A);
+		let frames: Vec<_>=manager.run_frame_all().into_iter()
+			.map(|(id,output)| (id,output.map(|o| o.frame)))
+			.collect();
+		assert_eq!(frames,vec![(a,Some(1)),(b,Some(0))]);
+		assert_eq!(manager.save(a),Some(Vec::new()));
+		manager.remove(a);
+		assert_eq!(manager.ids(),vec![b]);
+		assert!(manager.run_frame(a).is_none());
+	}
+}
@@ -1,38 +1,602 @@
 use gb::cartridge::Cartridge;
+use gb::serial::{self,Serial,SerialDevice};
+use gb::scheduler::Scheduler;
+use gb::peripheral::Peripheral;
+use gb::joypad::{Joypad,JoypadState};
+use gb::apu::Apu;
+use gb::interrupt::InterruptController;
+use gb::memory_map::{self,Device};
+use gb::coverage::{self,Coverage};
+use gb::sram_watch::SramWrite;
+use gb::io_registers;
+use std::cell::{Cell,RefCell};
+use std::collections::HashSet;
+use std::time::{SystemTime,UNIX_EPOCH};
 
+const VRAM_SIZE: usize = 0x2000;
+const WRAM_SIZE: usize = 0x2000;
+const OAM_SIZE: usize = 0xA0;
+const HRAM_SIZE: usize = 0x7F;
+
+//what WRAM and HRAM hold at power on. Real units come up with semi random
+//garbage and some games (and bugs) depend on it.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum RamInit{
+    Zero,
+    Ones,
+    //alternating blocks of 8 0x00 and 8 0xFF bytes.
+    Pattern,
+    Random(u64),
+}
+
+impl RamInit{
+    //parses zero, ff, pattern, random or random:<seed>. Random without a
+    //seed picks one from the clock.
+    pub fn from_name(name: &str)->Option<RamInit>{
+        match name{
+            "zero"    => Some(RamInit::Zero),
+            "ff"      => Some(RamInit::Ones),
+            "pattern" => Some(RamInit::Pattern),
+            "random"  => {
+                let now=SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                Some(RamInit::Random(now.as_secs()^now.subsec_nanos() as u64))
+            },
+            _ => name.strip_prefix("random:")
+                .and_then(|seed| seed.parse().ok())
+                .map(RamInit::Random),
+        }
+    }
+
+    fn fill(&self,ram: &mut [u8],state: &mut u64){
+        for (i,b) in ram.iter_mut().enumerate(){
+            *b=match *self{
+                RamInit::Zero      => 0x00,
+                RamInit::Ones      => 0xFF,
+                RamInit::Pattern   => if i&0x8==0 {0x00} else {0xFF},
+                RamInit::Random(_) => {
+                    //xorshift64*
+                    *state^=*state>>12;
+                    *state^=*state<<25;
+                    *state^=*state>>27;
+                    (state.wrapping_mul(0x2545F4914F6CDD1D)>>56) as u8
+                },
+            };
+        }
+    }
+}
+
+//what an access to an IO register nothing emulates yet does.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum UnimplementedIo{
+    //unimplemented!(), compat-run tells these apart from other panics.
+    Panic,
+    //warn the first time each register is touched, with the pc, then
+    //read 0xFF and drop writes so the game keeps going.
+    Warn,
+}
+
+impl UnimplementedIo{
+    pub fn from_name(name: &str)->Option<UnimplementedIo>{
+        match name{
+            "panic" => Some(UnimplementedIo::Panic),
+            "warn"  => Some(UnimplementedIo::Warn),
+            _       => None,
+        }
+    }
+}
+
+//what Interconnect schedules.
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum Event{
+    SerialTransfer,
+}
 
 pub struct Interconnect{
     cartridge: Cartridge,
+    vram: Vec<u8>,
+    wram: Vec<u8>,
+    oam: Vec<u8>,
+    hram: Vec<u8>,
+    serial: Serial,
+    joypad: Joypad,
+    apu: Apu,
+    ram_init: RamInit,
+    //reads take &self, so coverage tracking needs interior mutability.
+    coverage: Option<RefCell<Coverage>>,
+    //address of the instruction running, set on every fetch.
+    instruction_pc: Cell<u16>,
+    //cartridge ram writes not yet taken, when watching.
+    sram_writes: Option<Vec<SramWrite>>,
+    //IF and IE.
+    interrupts: InterruptController,
+    scheduler: Scheduler<Event>,
+    unimplemented_io: UnimplementedIo,
+    //registers already warned about. Reads take &self.
+    warned_io: RefCell<HashSet<u16>>,
 }
 
 impl Interconnect{
-    pub fn new(cart:Vec<u8>)->Self{
+    pub fn new(cart:Vec<u8>,ram_init: RamInit)->Self{
+        let mut wram=vec![0;WRAM_SIZE];
+        let mut hram=vec![0;HRAM_SIZE];
+        //xorshift gets stuck on 0.
+        let mut state=match ram_init{
+            RamInit::Random(seed) => seed|1,
+            _                     => 0,
+        };
+        ram_init.fill(&mut wram,&mut state);
+        ram_init.fill(&mut hram,&mut state);
         Interconnect{
             cartridge: Cartridge::new(cart),
+            vram: vec![0;VRAM_SIZE],
+            wram,
+            oam: vec![0;OAM_SIZE],
+            hram,
+            serial: Serial::new(),
+            joypad: Joypad::new(),
+            apu: Apu::new(),
+            ram_init,
+            coverage: None,
+            instruction_pc: Cell::new(0),
+            sram_writes: None,
+            interrupts: InterruptController::new(),
+            scheduler: Scheduler::new(),
+            unimplemented_io: UnimplementedIo::Warn,
+            warned_io: RefCell::new(HashSet::new()),
+        }
+    }
+
+    //power cycles with another cartridge. Ram is initialised again with the
+    //same policy, the link port device and tooling stay as they were.
+    pub fn load_cartridge(&mut self,cart:Vec<u8>){
+        let mut serial=::std::mem::take(&mut self.serial);
+        serial.reset();
+        let coverage=self.coverage.is_some();
+        let sram_writes=self.sram_writes.is_some();
+        let keys=self.joypad.state();
+        let unimplemented_io=self.unimplemented_io;
+        *self=Interconnect::new(cart,self.ram_init);
+        self.serial=serial;
+        self.unimplemented_io=unimplemented_io;
+        self.joypad.set_state(keys);
+        if coverage {
+            self.enable_coverage();
+        }
+        if sram_writes {
+            self.watch_sram();
+        }
+    }
+
+    pub fn set_unimplemented_io(&mut self,policy: UnimplementedIo){
+        self.unimplemented_io=policy;
+    }
+
+    //the policy (and seed) ram was initialised with, to reproduce a run.
+    pub fn ram_init(&self)->RamInit{
+        self.ram_init
+    }
+
+    pub fn cartridge(&self)->&Cartridge{
+        &self.cartridge
+    }
+
+    pub fn cartridge_mut(&mut self)->&mut Cartridge{
+        &mut self.cartridge
+    }
+
+    //0x8000-0x9FFF, see gb::video for typed views.
+    pub fn vram(&self)->&[u8]{
+        &self.vram
+    }
+
+    //0xC000-0xDFFF
+    pub fn wram(&self)->&[u8]{
+        &self.wram
+    }
+
+    //0xFF80-0xFFFE
+    pub fn hram(&self)->&[u8]{
+        &self.hram
+    }
+
+    //0xFE00-0xFE9F
+    pub fn oam(&self)->&[u8]{
+        &self.oam
+    }
+
+    //plugs a device into the link port.
+    pub fn connect_serial(&mut self,device: Box<dyn SerialDevice>){
+        self.serial.connect(device);
+    }
+
+    //everything behind the IO registers, new hardware goes here.
+    fn peripherals(&self)->[&dyn Peripheral;3]{
+        [&self.serial,&self.joypad,&self.apu]
+    }
+
+    fn peripherals_mut(&mut self)->[&mut dyn Peripheral;3]{
+        [&mut self.serial,&mut self.joypad,&mut self.apu]
+    }
+
+    fn peripheral(&self,address:u16)->Option<&dyn Peripheral>{
+        self.peripherals().iter().cloned().find(|p| p.handles(address))
+    }
+
+    //advances the peripherals by the cycles the last instruction took.
+    pub fn step(&mut self,cycles: u32){
+        self.cartridge.step(cycles);
+        self.scheduler.advance(cycles as u64);
+        while let Some(event)=self.scheduler.pop_due() {
+            match event{
+                Event::SerialTransfer => self.serial.complete_transfer(),
+            }
+        }
+        let requests=self.peripherals_mut().iter_mut()
+            .fold(0,|requests,p| {
+                p.step(cycles);
+                requests | p.pending_interrupt()
+            });
+        self.interrupts.request(requests);
+    }
+
+    //bytes sent over the link port since the last call.
+    pub fn take_serial_sent(&mut self)->Vec<u8>{
+        self.serial.take_sent()
+    }
+
+    pub fn set_joypad_state(&mut self,state: JoypadState){
+        self.joypad.set_state(state);
+    }
+
+    pub fn apu(&self)->&Apu{
+        &self.apu
+    }
+
+    pub fn joypad(&self)->&Joypad{
+        &self.joypad
+    }
+
+    pub fn joypad_mut(&mut self)->&mut Joypad{
+        &mut self.joypad
+    }
+
+    pub fn interrupts(&self)->&InterruptController{
+        &self.interrupts
+    }
+
+    pub fn interrupts_mut(&mut self)->&mut InterruptController{
+        &mut self.interrupts
+    }
+
+    //starts recording which rom bytes are executed and read.
+    pub fn enable_coverage(&mut self){
+        self.coverage=Some(RefCell::new(Coverage::new(self.cartridge.rom().len())));
+    }
+
+    pub fn coverage(&self)->Option<Coverage>{
+        self.coverage.as_ref().map(|c| c.borrow().clone())
+    }
+
+    fn mark_coverage(&self,address:u16,flag: u8){
+        if let Some(ref coverage)=self.coverage {
+            if let Some(offset)=self.cartridge.rom_offset(address) {
+                coverage.borrow_mut().mark(offset,flag);
+            }
+        }
+    }
+
+    //starts recording writes to cartridge ram.
+    pub fn watch_sram(&mut self){
+        self.sram_writes=Some(Vec::new());
+    }
+
+    //writes since the last call, oldest first.
+    pub fn take_sram_writes(&mut self)->Vec<SramWrite>{
+        self.sram_writes.as_mut().map_or_else(Vec::new,::std::mem::take)
+    }
+
+    //reads an opcode for the CPU.
+    pub fn fetch(&self,address:u16)->u8{
+        self.instruction_pc.set(address);
+        if address<0x8000 {
+            self.mark_coverage(address,coverage::EXECUTED);
+            return self.cartridge.read(address);
         }
+        self.read(address)
     }
 
     //reads 8bits
     pub fn read(&self,address:u16)->u8{
-        //TODO finish
-        match address{
-            0x0000...0x7FFF |
-            0xA000...0xBFFF => self.cartridge.read(address),
-            _               => unimplemented!(),
+        let region=memory_map::region(address);
+        if !region.readable {
+            return 0xFF;
+        }
+        let offset=region.offset(address);
+        match region.device{
+            Device::Cartridge => {
+                if address<0x8000 {
+                    self.mark_coverage(address,coverage::READ);
+                }
+                self.cartridge.read(address)
+            },
+            Device::Vram      => self.vram[offset],
+            Device::Wram      => self.wram[offset],
+            Device::Oam       => self.oam[offset],
+            Device::Hram      => self.hram[offset],
+            Device::Io        => self.read_io(address),
+            Device::Interrupt => self.interrupts.read_ie(),
+            Device::Unusable  => 0xFF,
+        }
+    }
+
+    //reads without side effects and without panicking on unimplemented
+    //registers (they read 0xFF), for tooling.
+    pub fn peek(&self,address:u16)->u8{
+        match memory_map::region(address).device{
+            Device::Cartridge => self.cartridge.read(address),
+            Device::Io        => self.peek_io(address),
+            _                 => self.read(address),
         }
     }
 
-    //reads 16bits
+    //whether a device answers at `address`, reads elsewhere are open bus.
+    pub fn is_mapped(&self,address:u16)->bool{
+        let region=memory_map::region(address);
+        match region.device{
+            Device::Cartridge if address>=0xA000 => self.cartridge.ram_mapped(address),
+            _                                    => region.readable,
+        }
+    }
+
+    //reads 16bits, little endian
     pub fn read_16bits(&self,address:u16)->u16{
-        (self.read(address) as u16) << 8 | (self.read(address+1) as u16)
+        (self.read(address) as u16) | (self.read(address.wrapping_add(1)) as u16) << 8
     }
 
     pub fn write(&mut self,address:u16,data:u8){
-        //TODO finish
-        match address{
-            0x0000...0x7FFF |
-            0xA000...0xBFFF => self.cartridge.write(address,data),
-            _               => unimplemented!(),
+        let region=memory_map::region(address);
+        if !region.writable {
+            return;
+        }
+        let offset=region.offset(address);
+        match region.device{
+            Device::Cartridge => {
+                if let (Some(writes),0xA000..=0xBFFF)=(self.sram_writes.as_mut(),address) {
+                    writes.push(SramWrite{pc: self.instruction_pc.get(),
+                        bank: self.cartridge.current_ram_bank(),address,data});
+                }
+                self.cartridge.write(address,data);
+            },
+            Device::Vram      => self.vram[offset]=data,
+            Device::Wram      => self.wram[offset]=data,
+            Device::Oam       => self.oam[offset]=data,
+            Device::Hram      => self.hram[offset]=data,
+            Device::Io        => self.write_io(address,data),
+            Device::Interrupt => self.interrupts.write_ie(data),
+            Device::Unusable  => {},
         }
     }
-}
\ No newline at end of file
+
+    fn read_io(&self,address:u16)->u8{
+        if address==0xFF0F {
+            return self.interrupts.read_if();
+        }
+        match self.peripheral(address){
+            Some(p) => p.read(address),
+            None    => {
+                self.unimplemented_io_access(address,"read");
+                0xFF
+            },
+        }
+    }
+
+    fn peek_io(&self,address:u16)->u8{
+        if address==0xFF0F {
+            return self.interrupts.read_if();
+        }
+        self.peripheral(address).map_or(0xFF,|p| p.read(address))
+    }
+
+    fn write_io(&mut self,address:u16,data:u8){
+        if address==0xFF0F {
+            self.interrupts.write_if(data);
+            return;
+        }
+        match self.peripherals_mut().iter_mut().find(|p| p.handles(address)){
+            Some(p) => p.write(address,data),
+            None    => self.unimplemented_io_access(address,"write"),
+        }
+        //writing SC starts the transfer over, or stops it
+        if address==0xFF02 {
+            self.scheduler.cancel(|&event| event==Event::SerialTransfer);
+            if self.serial.internal_transfer() {
+                self.scheduler.schedule_in(serial::TRANSFER_CYCLES,Event::SerialTransfer);
+            }
+        }
+    }
+
+    fn unimplemented_io_access(&self,address: u16,access: &str){
+        let name=io_registers::io_register(address).map_or(String::new(),|r| format!(" ({})",r.name));
+        let message=format!("IO {} of {:04X}{} at PC {:04X}",access,address,name,self.instruction_pc.get());
+        match self.unimplemented_io{
+            UnimplementedIo::Panic => unimplemented!("{}",message),
+            UnimplementedIo::Warn  => if self.warned_io.borrow_mut().insert(address) {
+                eprintln!("Warning: unimplemented {}",message);
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+
+    use super::{Interconnect,RamInit,UnimplementedIo};
+    use gb::memory_map::{self,MEMORY_MAP,Device,DumpFormat};
+
+    fn interconnect()->Interconnect{
+        //MBC1 with 8KB of ram so every region has something behind it
+        let mut rom=vec![0;0x8000];
+        rom[0x147]=0x03;
+        rom[0x149]=0x02;
+        rom[0x0000]=0x11;
+        rom[0x7FFF]=0x22;
+        Interconnect::new(rom,RamInit::Zero)
+    }
+
+    #[test]
+    fn map_covers_every_address_once(){
+        let mut next=0u32;
+        for region in MEMORY_MAP.iter(){
+            assert_eq!(region.start as u32,next,"gap or overlap before {}",region.name);
+            assert!(region.end>=region.start);
+            next=region.end as u32+1;
+        }
+        assert_eq!(next,0x10000);
+        for address in 0..0x10000u32{
+            let region=memory_map::region(address as u16);
+            assert!(region.start as u32<=address && address<=region.end as u32);
+        }
+    }
+
+    #[test]
+    fn ram_regions_route_to_their_device(){
+        let mut inter=interconnect();
+        for region in MEMORY_MAP.iter(){
+            let backed=matches!(region.device,Device::Vram | Device::Wram | Device::Oam | Device::Hram);
+            if !backed || region.mirror_of.is_some() {
+                continue;
+            }
+            for &address in &[region.start,region.end]{
+                inter.write(address,0x5A);
+                assert_eq!(inter.read(address),0x5A,"{} at 0x{:04X}",region.name,address);
+                inter.write(address,0xA5);
+                assert_eq!(inter.read(address),0xA5,"{} at 0x{:04X}",region.name,address);
+            }
+        }
+        assert_eq!(inter.vram()[0x1FFF],0xA5);
+        assert_eq!(inter.oam()[0x9F],0xA5);
+    }
+
+    #[test]
+    fn cartridge_regions(){
+        let mut inter=interconnect();
+        assert_eq!(inter.read(0x0000),0x11);
+        assert_eq!(inter.read(0x7FFF),0x22);
+        inter.write(0xA000,0x33);
+        inter.write(0xBFFF,0x44);
+        assert_eq!(inter.read(0xA000),0x33);
+        assert_eq!(inter.read(0xBFFF),0x44);
+    }
+
+    #[test]
+    fn echo_mirrors_wram(){
+        let mut inter=interconnect();
+        inter.write(0xC123,0x77);
+        assert_eq!(inter.read(0xE123),0x77);
+        inter.write(0xFDFF,0x88);
+        assert_eq!(inter.read(0xDDFF),0x88);
+    }
+
+    #[test]
+    fn unusable_region(){
+        let mut inter=interconnect();
+        for address in 0xFEA0..0xFF00{
+            inter.write(address,0x00);
+            assert_eq!(inter.read(address),0xFF);
+        }
+    }
+
+    #[test]
+    fn read_16bits_is_little_endian(){
+        let mut inter=interconnect();
+        inter.write(0xC000,0x50);
+        inter.write(0xC001,0x01);
+        assert_eq!(inter.read_16bits(0xC000),0x0150);
+    }
+
+    #[test]
+    fn dump_marks_mirrors_and_unmapped(){
+        let mut inter=interconnect();
+        inter.write(0xC001,0xAB);
+        let peek=|a| if inter.is_mapped(a) {Some(inter.peek(a))} else {None};
+        let dump=memory_map::format_memory_dump(0xC001,0xC002,&peek);
+        assert_eq!(dump,vec![format!("C000 |    AB 00{} | WRAM"," ".repeat(13*3))]);
+        let dump=memory_map::format_memory_dump(0xE000,0xE001,&peek);
+        assert!(dump[0].starts_with("E000 |~00~AB    "));
+        let dump=memory_map::format_memory_dump(0xFE9E,0xFEA1,&peek);
+        assert_eq!(dump[0],format!("FE90 |{} 00 00 | OAM"," ".repeat(14*3)));
+        assert_eq!(dump[1],format!("FEA0 | -- --{} | Unusable"," ".repeat(14*3)));
+        let no_ram=Interconnect::new(vec![0;0x8000],RamInit::Zero);
+        assert!(!no_ram.is_mapped(0xA000));
+        assert!(inter.is_mapped(0xA000));
+    }
+
+    #[test]
+    fn dump_formats(){
+        let mut inter=interconnect();
+        for (i,&byte) in [0x34,0x12,0xFF,0x99,0x7A].iter().enumerate(){
+            inter.write(0xC000+i as u16,byte);
+        }
+        let peek=|a| if inter.is_mapped(a) {Some(inter.peek(a))} else {None};
+        let dump=|format| memory_map::format_memory_dump_as(0xC000,0xC004,format,&peek).remove(0);
+        assert!(dump(DumpFormat::Words).starts_with("C000 | 1234 99FF      "));
+        assert!(dump(DumpFormat::Signed).starts_with("C000 |   52   18   -1 -103  122     "));
+        assert!(dump(DumpFormat::Bcd).starts_with("C000 | 34 12 ?? 99 7?    "));
+        assert_eq!(dump(DumpFormat::Bytes),memory_map::format_memory_dump(0xC000,0xC004,&peek).remove(0));
+        let dump=memory_map::format_memory_dump_as(0xFE9E,0xFEA1,DumpFormat::Words,&peek);
+        assert_eq!(dump[1],format!("FEA0 | ----{} | Unusable"," ".repeat(7*5)));
+    }
+
+    #[test]
+    fn sram_writes_are_attributed(){
+        let mut inter=interconnect();
+        inter.write(0xA000,0x01);
+        inter.watch_sram();
+        inter.fetch(0x0150);
+        inter.write(0xC000,0x02);
+        inter.write(0xA123,0x03);
+        let writes=inter.take_sram_writes();
+        assert_eq!(writes.len(),1);
+        assert_eq!((writes[0].pc,writes[0].bank,writes[0].address,writes[0].data),(0x0150,0,0xA123,0x03));
+        assert!(inter.take_sram_writes().is_empty());
+    }
+
+    #[test]
+    fn unimplemented_io_warns_once(){
+        let mut inter=interconnect();
+        //DIV, no timer yet
+        inter.write(0xFF04,0x12);
+        assert_eq!(inter.read(0xFF04),0xFF);
+        assert_eq!(inter.read(0xFF04),0xFF);
+        assert_eq!(inter.warned_io.borrow().len(),1);
+        inter.set_unimplemented_io(UnimplementedIo::Panic);
+        let read=::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| inter.read(0xFF04)));
+        assert!(read.is_err());
+        let message=::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| inter.read(0xFF05))).unwrap_err();
+        assert!(message.downcast_ref::<String>().unwrap().contains("(TIMA)"));
+    }
+
+    #[test]
+    fn unusable_area(){
+        let mut inter=interconnect();
+        inter.write(0xFEA0,0x12);
+        assert_eq!(inter.read(0xFEA0),0xFF);
+        assert_eq!(inter.read(0xFEFF),0xFF);
+    }
+
+    #[test]
+    fn interrupt_registers(){
+        let mut inter=interconnect();
+        inter.write(0xFF0F,0x00);
+        assert_eq!(inter.read(0xFF0F),0xE0);
+        inter.write(0xFFFF,0xFF);
+        assert_eq!(inter.read(0xFFFF),0xFF);
+        assert_eq!(inter.peek(0xFFFF),0xFF);
+        //a finished serial transfer raises IF bit 3
+        inter.write(0xFF02,0x81);
+        inter.step(8*512-4);
+        assert_eq!(inter.read(0xFF0F),0xE0);
+        inter.step(4);
+        assert_eq!(inter.read(0xFF0F),0xE8);
+    }
+}
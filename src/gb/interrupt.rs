@@ -0,0 +1,92 @@
+//IF (0xFF0F) and IE (0xFFFF). Only the low 5 bits of IF exist, the upper
+//3 read back as 1, while IE stores all 8 bits even though only 5 mean
+//anything. Test roms check both.
+
+pub const VBLANK: u8 = 0x01;
+pub const STAT: u8 = 0x02;
+pub const TIMER: u8 = 0x04;
+pub const SERIAL: u8 = 0x08;
+pub const JOYPAD: u8 = 0x10;
+
+const IF_MASK: u8 = 0x1F;
+
+#[derive(Debug,Default)]
+pub struct InterruptController{
+	flags: u8,
+	enable: u8,
+}
+
+impl InterruptController{
+	pub fn new()->Self{
+		InterruptController::default()
+	}
+
+	pub fn read_if(&self)->u8{
+		self.flags|!IF_MASK
+	}
+
+	pub fn write_if(&mut self,data: u8){
+		self.flags=data&IF_MASK;
+	}
+
+	pub fn read_ie(&self)->u8{
+		self.enable
+	}
+
+	pub fn write_ie(&mut self,data: u8){
+		self.enable=data;
+	}
+
+	//raises IF bits for hardware events.
+	pub fn request(&mut self,interrupts: u8){
+		self.flags|=interrupts&IF_MASK;
+	}
+
+	//requested and enabled interrupts, what the cpu would service.
+	pub fn pending(&self)->u8{
+		self.flags&self.enable&IF_MASK
+	}
+
+	//clears the bit of an interrupt being serviced.
+	pub fn acknowledge(&mut self,interrupt: u8){
+		self.flags&=!interrupt;
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{InterruptController,SERIAL,TIMER,VBLANK};
+
+	#[test]
+	fn if_upper_bits_read_as_one(){
+		let mut ic=InterruptController::new();
+		assert_eq!(ic.read_if(),0xE0);
+		ic.write_if(0xFF);
+		assert_eq!(ic.read_if(),0xFF);
+		ic.write_if(0x00);
+		ic.request(0xFF);
+		assert_eq!(ic.read_if(),0xFF);
+	}
+
+	#[test]
+	fn ie_stores_all_bits(){
+		let mut ic=InterruptController::new();
+		ic.write_ie(0xFF);
+		assert_eq!(ic.read_ie(),0xFF);
+		ic.write_ie(0xE2);
+		assert_eq!(ic.read_ie(),0xE2);
+	}
+
+	#[test]
+	fn pending_needs_request_and_enable(){
+		let mut ic=InterruptController::new();
+		ic.request(SERIAL|TIMER);
+		assert_eq!(ic.pending(),0);
+		ic.write_ie(0xE0|SERIAL|VBLANK);
+		assert_eq!(ic.pending(),SERIAL);
+		ic.acknowledge(SERIAL);
+		assert_eq!(ic.pending(),0);
+		assert_eq!(ic.read_if(),0xE0|TIMER);
+	}
+}
@@ -0,0 +1,73 @@
+//The IO registers (0xFF00-0xFF7F) as data: names for tooling, and the bits
+//that don't exist or are write only, which always read back as 1. Test
+//roms read every register back to check the latter.
+
+#[derive(Debug)]
+pub struct IoRegister{
+	pub address: u16,
+	pub name: &'static str,
+	//bits that read as 1 whatever was written.
+	pub read_mask: u8,
+}
+
+macro_rules! io{
+	($address:expr,$name:expr,$mask:expr) => {
+		IoRegister{address: $address,name: $name,read_mask: $mask}
+	}
+}
+
+pub const IO_REGISTERS: [IoRegister;42] = [
+	io!(0xFF00,"P1",0xC0),
+	io!(0xFF01,"SB",0x00),
+	io!(0xFF02,"SC",0x7E),
+	io!(0xFF04,"DIV",0x00),
+	io!(0xFF05,"TIMA",0x00),
+	io!(0xFF06,"TMA",0x00),
+	io!(0xFF07,"TAC",0xF8),
+	io!(0xFF0F,"IF",0xE0),
+	io!(0xFF10,"NR10",0x80),
+	io!(0xFF11,"NR11",0x3F),
+	io!(0xFF12,"NR12",0x00),
+	io!(0xFF13,"NR13",0xFF),
+	io!(0xFF14,"NR14",0xBF),
+	io!(0xFF16,"NR21",0x3F),
+	io!(0xFF17,"NR22",0x00),
+	io!(0xFF18,"NR23",0xFF),
+	io!(0xFF19,"NR24",0xBF),
+	io!(0xFF1A,"NR30",0x7F),
+	io!(0xFF1B,"NR31",0xFF),
+	io!(0xFF1C,"NR32",0x9F),
+	io!(0xFF1D,"NR33",0xFF),
+	io!(0xFF1E,"NR34",0xBF),
+	io!(0xFF20,"NR41",0xFF),
+	io!(0xFF21,"NR42",0x00),
+	io!(0xFF22,"NR43",0x00),
+	io!(0xFF23,"NR44",0xBF),
+	io!(0xFF24,"NR50",0x00),
+	io!(0xFF25,"NR51",0x00),
+	io!(0xFF26,"NR52",0x70),
+	io!(0xFF40,"LCDC",0x00),
+	io!(0xFF41,"STAT",0x80),
+	io!(0xFF42,"SCY",0x00),
+	io!(0xFF43,"SCX",0x00),
+	io!(0xFF44,"LY",0x00),
+	io!(0xFF45,"LYC",0x00),
+	io!(0xFF46,"DMA",0x00),
+	io!(0xFF47,"BGP",0x00),
+	io!(0xFF48,"OBP0",0x00),
+	io!(0xFF49,"OBP1",0x00),
+	io!(0xFF4A,"WY",0x00),
+	io!(0xFF4B,"WX",0x00),
+	//writing it unmaps the boot rom, it always reads 0xFF.
+	io!(0xFF50,"BOOT",0xFF),
+];
+
+pub fn io_register(address: u16)->Option<&'static IoRegister>{
+	IO_REGISTERS.iter().find(|r| r.address==address)
+}
+
+//addresses missing from the table aren't registers and read 0xFF. Wave
+//ram (0xFF30-0xFF3F) is memory rather than registers and isn't in it.
+pub fn read_mask(address: u16)->u8{
+	io_register(address).map_or(0xFF,|r| r.read_mask)
+}
@@ -0,0 +1,212 @@
+//The whole SM83 instruction set as data: mnemonic, operands, length,
+//cycles and flag effects of every opcode, 0xCB prefixed ones included, for
+//tools that need more than the decoder. Built from the bit fields of the
+//opcode (x = bits 7-6, y = bits 5-3, z = bits 2-0), the way the hardware
+//decodes them, rather than typed out 500 times. Names and operands follow
+//the usual opcode tables ("LD A,(HL+)", "JR NZ,r8").
+
+const R8: [&str;8] = ["B","C","D","E","H","L","(HL)","A"];
+const R16: [&str;4] = ["BC","DE","HL","SP"];
+const R16_INDIRECT: [&str;4] = ["(BC)","(DE)","(HL+)","(HL-)"];
+const R16_STACK: [&str;4] = ["BC","DE","HL","AF"];
+const CONDITIONS: [&str;4] = ["NZ","Z","NC","C"];
+const BITS: [&str;8] = ["0","1","2","3","4","5","6","7"];
+const RST_VECTORS: [&str;8] = ["00H","08H","10H","18H","20H","28H","30H","38H"];
+//ALU operations on A, with whether they name A as the first operand.
+const ALU: [(&str,bool,&str);8] = [
+	("ADD",true,"Z0HC"),("ADC",true,"Z0HC"),("SUB",false,"Z1HC"),("SBC",true,"Z1HC"),
+	("AND",false,"Z010"),("XOR",false,"Z000"),("OR",false,"Z000"),("CP",false,"Z1HC"),
+];
+const ROTATES: [&str;8] = ["RLC","RRC","RL","RR","SLA","SRA","SWAP","SRL"];
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Instruction{
+	pub opcode: u8,
+	//comes after a 0xCB prefix.
+	pub prefixed: bool,
+	pub mnemonic: &'static str,
+	pub operands: Vec<&'static str>,
+	//bytes, counting the prefix and immediates.
+	pub length: u8,
+	//cycles, for conditional instructions when the condition fails.
+	pub cycles: u8,
+	//cycles when the condition holds.
+	pub cycles_taken: Option<u8>,
+	//Z, N, H and C: the flag's letter when set from the result, 0 or 1
+	//when forced, - when left alone.
+	pub flags: &'static str,
+}
+
+impl Instruction{
+	fn new(opcode: u8,mnemonic: &'static str,operands: &[&'static str],length: u8,cycles: u8,flags: &'static str)->Self{
+		Instruction{opcode,prefixed: false,mnemonic,operands: operands.to_vec(),length,cycles,
+			cycles_taken: None,flags}
+	}
+
+	fn taken(mut self,cycles: u8)->Self{
+		self.cycles_taken=Some(cycles);
+		self
+	}
+
+	//"LD A,(HL+)"
+	pub fn text(&self)->String{
+		if self.operands.is_empty() {
+			self.mnemonic.to_string()
+		}
+		else {
+			format!("{} {}",self.mnemonic,self.operands.join(","))
+		}
+	}
+
+	pub fn to_json(&self)->String{
+		let operands: Vec<String>=self.operands.iter().map(|o| format!("\"{}\"",o)).collect();
+		let taken=self.cycles_taken.map_or(String::from("null"),|c| c.to_string());
+		format!("{{\"opcode\":{},\"prefixed\":{},\"mnemonic\":\"{}\",\"operands\":[{}],\"length\":{},\"cycles\":{},\"cycles_taken\":{},\"flags\":\"{}\"}}",
+			self.opcode,self.prefixed,self.mnemonic,operands.join(","),self.length,self.cycles,taken,self.flags)
+	}
+}
+
+//the unprefixed instruction, None for the 11 opcodes that lock up the cpu.
+pub fn instruction(opcode: u8)->Option<Instruction>{
+	let (x,y,z)=(opcode>>6,(opcode>>3&7) as usize,(opcode&7) as usize);
+	let (p,q)=(y>>1,y&1);
+	let new=|mnemonic,operands: &[&'static str],length,cycles,flags| Instruction::new(opcode,mnemonic,operands,length,cycles,flags);
+	//(HL) operands cost a memory access
+	let r8_cycles=|r: usize,cycles: u8,hl: u8| if r==6 {hl} else {cycles};
+	Some(match (x,z){
+		(0,0) => match y{
+			0 => new("NOP",&[],1,4,"----"),
+			1 => new("LD",&["(a16)","SP"],3,20,"----"),
+			2 => new("STOP",&["0"],2,4,"----"),
+			3 => new("JR",&["r8"],2,12,"----"),
+			_ => new("JR",&[CONDITIONS[y-4],"r8"],2,8,"----").taken(12),
+		},
+		(0,1) if q==0 => new("LD",&[R16[p],"d16"],3,12,"----"),
+		(0,1)         => new("ADD",&["HL",R16[p]],1,8,"-0HC"),
+		(0,2) if q==0 => new("LD",&[R16_INDIRECT[p],"A"],1,8,"----"),
+		(0,2)         => new("LD",&["A",R16_INDIRECT[p]],1,8,"----"),
+		(0,3)         => new(if q==0 {"INC"} else {"DEC"},&[R16[p]],1,8,"----"),
+		(0,4)         => new("INC",&[R8[y]],1,r8_cycles(y,4,12),"Z0H-"),
+		(0,5)         => new("DEC",&[R8[y]],1,r8_cycles(y,4,12),"Z1H-"),
+		(0,6)         => new("LD",&[R8[y],"d8"],2,r8_cycles(y,8,12),"----"),
+		(0,_)         => {
+			let (mnemonic,flags)=[("RLCA","000C"),("RRCA","000C"),("RLA","000C"),("RRA","000C"),
+				("DAA","Z-0C"),("CPL","-11-"),("SCF","-001"),("CCF","-00C")][y];
+			new(mnemonic,&[],1,4,flags)
+		},
+		(1,_) if y==6 && z==6 => new("HALT",&[],1,4,"----"),
+		(1,_)         => new("LD",&[R8[y],R8[z]],1,if y==6 || z==6 {8} else {4},"----"),
+		(2,_)         => {
+			let (mnemonic,with_a,flags)=ALU[y];
+			let operands: &[&'static str]=if with_a {&["A",R8[z]]} else {&[R8[z]]};
+			new(mnemonic,operands,1,r8_cycles(z,4,8),flags)
+		},
+		(_,0) => match y{
+			0..=3 => new("RET",&[CONDITIONS[y]],1,8,"----").taken(20),
+			4     => new("LDH",&["(a8)","A"],2,12,"----"),
+			5     => new("ADD",&["SP","r8"],2,16,"00HC"),
+			6     => new("LDH",&["A","(a8)"],2,12,"----"),
+			_     => new("LD",&["HL","SP+r8"],2,12,"00HC"),
+		},
+		(_,1) if q==0 => new("POP",&[R16_STACK[p]],1,12,if p==3 {"ZNHC"} else {"----"}),
+		(_,1) => match p{
+			0 => new("RET",&[],1,16,"----"),
+			1 => new("RETI",&[],1,16,"----"),
+			2 => new("JP",&["(HL)"],1,4,"----"),
+			_ => new("LD",&["SP","HL"],1,8,"----"),
+		},
+		(_,2) => match y{
+			0..=3 => new("JP",&[CONDITIONS[y],"a16"],3,12,"----").taken(16),
+			4     => new("LD",&["(C)","A"],1,8,"----"),
+			5     => new("LD",&["(a16)","A"],3,16,"----"),
+			6     => new("LD",&["A","(C)"],1,8,"----"),
+			_     => new("LD",&["A","(a16)"],3,16,"----"),
+		},
+		(_,3) => match y{
+			0 => new("JP",&["a16"],3,16,"----"),
+			1 => new("PREFIX",&["CB"],1,4,"----"),
+			6 => new("DI",&[],1,4,"----"),
+			7 => new("EI",&[],1,4,"----"),
+			_ => return None,
+		},
+		(_,4) if y<4  => new("CALL",&[CONDITIONS[y],"a16"],3,12,"----").taken(24),
+		(_,5) if q==0 => new("PUSH",&[R16_STACK[p]],1,16,"----"),
+		(_,5) if p==0 => new("CALL",&["a16"],3,24,"----"),
+		(_,6)         => {
+			let (mnemonic,with_a,flags)=ALU[y];
+			let operands: &[&'static str]=if with_a {&["A","d8"]} else {&["d8"]};
+			new(mnemonic,operands,2,8,flags)
+		},
+		(_,7)         => new("RST",&[RST_VECTORS[y]],1,16,"----"),
+		_             => return None,
+	})
+}
+
+//the instruction after a 0xCB prefix, length and cycles include it.
+pub fn prefixed(opcode: u8)->Instruction{
+	let (x,y,z)=(opcode>>6,(opcode>>3&7) as usize,(opcode&7) as usize);
+	let hl=z==6;
+	let mut instruction=match x{
+		0 => Instruction::new(opcode,ROTATES[y],&[R8[z]],2,if hl {16} else {8},if y==6 {"Z000"} else {"Z00C"}),
+		1 => Instruction::new(opcode,"BIT",&[BITS[y],R8[z]],2,if hl {12} else {8},"Z01-"),
+		2 => Instruction::new(opcode,"RES",&[BITS[y],R8[z]],2,if hl {16} else {8},"----"),
+		_ => Instruction::new(opcode,"SET",&[BITS[y],R8[z]],2,if hl {16} else {8},"----"),
+	};
+	instruction.prefixed=true;
+	instruction
+}
+
+//every valid instruction, unprefixed first, each in opcode order.
+pub fn all()->Vec<Instruction>{
+	(0..=255).filter_map(instruction)
+		.chain((0..=255).map(prefixed))
+		.collect()
+}
+
+//a JSON array of every instruction, one per line.
+pub fn to_json(instructions: &[Instruction])->String{
+	let lines: Vec<String>=instructions.iter().map(|i| format!("  {}",i.to_json())).collect();
+	format!("[\n{}\n]\n",lines.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{instruction,prefixed,all};
+	use gb::opcode::Opcode;
+
+	#[test]
+	fn known_instructions(){
+		let text=|opcode| instruction(opcode).unwrap().text();
+		assert_eq!(text(0x00),"NOP");
+		assert_eq!(text(0x2A),"LD A,(HL+)");
+		assert_eq!(text(0x76),"HALT");
+		assert_eq!(text(0x9E),"SBC A,(HL)");
+		assert_eq!(text(0xE0),"LDH (a8),A");
+		assert_eq!(text(0xF1),"POP AF");
+		assert_eq!(text(0xFF),"RST 38H");
+		let jr=instruction(0x20).unwrap();
+		assert_eq!((jr.length,jr.cycles,jr.cycles_taken),(2,8,Some(12)));
+		assert_eq!(instruction(0x34).unwrap().cycles,12);
+		assert_eq!(prefixed(0x7E).text(),"BIT 7,(HL)");
+		assert_eq!((prefixed(0x7E).cycles,prefixed(0x7E).flags),(12,"Z01-"));
+		assert_eq!(prefixed(0x37).text(),"SWAP A");
+	}
+
+	#[test]
+	fn eleven_holes(){
+		let holes: Vec<u8>=(0..=255).filter(|&op| instruction(op).is_none()).collect();
+		assert_eq!(holes,vec![0xD3,0xDB,0xDD,0xE3,0xE4,0xEB,0xEC,0xED,0xF4,0xFC,0xFD]);
+		assert_eq!(all().len(),245+256);
+	}
+
+	//the decoder's cycle table and this one must agree.
+	#[test]
+	fn agrees_with_the_opcode_table(){
+		for opcode in (0..=255).filter_map(Opcode::decode){
+			let isa=instruction(opcode as u8).unwrap();
+			let (cycles,taken)=opcode.cycles();
+			assert_eq!((isa.cycles as usize,isa.cycles_taken.map(|c| c as usize)),(cycles,taken),"{:?}",opcode);
+		}
+	}
+}
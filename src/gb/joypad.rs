@@ -0,0 +1,222 @@
+//P1 (0xFF00). Games pull bit 4 low to read the direction keys and/or bit 5
+//low to read the buttons, and get the pressed ones back as 0s in the low
+//nibble. A key going down on a selected line raises the joypad interrupt.
+
+use gb::peripheral::Peripheral;
+use gb::interrupt;
+
+use std::ops::{BitOr,BitOrAssign};
+use std::fmt::{self,Display,Formatter};
+
+const P1: u16 = 0xFF00;
+const SELECT_DIRECTIONS: u8 = 0x10;
+const SELECT_BUTTONS: u8 = 0x20;
+
+//every key held at one point in time, set as a whole once per frame. A
+//plain byte, so it records, replays and goes over the network as is.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub struct JoypadState(u8);
+
+impl JoypadState{
+	pub const A: JoypadState = JoypadState(0x01);
+	pub const B: JoypadState = JoypadState(0x02);
+	pub const SELECT: JoypadState = JoypadState(0x04);
+	pub const START: JoypadState = JoypadState(0x08);
+	pub const RIGHT: JoypadState = JoypadState(0x10);
+	pub const LEFT: JoypadState = JoypadState(0x20);
+	pub const UP: JoypadState = JoypadState(0x40);
+	pub const DOWN: JoypadState = JoypadState(0x80);
+
+	pub fn from_bits(bits: u8)->Self{
+		JoypadState(bits)
+	}
+
+	pub fn bits(self)->u8{
+		self.0
+	}
+
+	pub fn contains(self,keys: JoypadState)->bool{
+		self.0&keys.0==keys.0
+	}
+
+	pub fn insert(&mut self,keys: JoypadState){
+		self.0|=keys.0;
+	}
+
+	pub fn remove(&mut self,keys: JoypadState){
+		self.0&=!keys.0;
+	}
+}
+
+//input display for streams and status lines: "<^v> B A SELECT START"
+//with the keys not held blanked out as dots, so it keeps its width.
+impl Display for JoypadState{
+	fn fmt(&self,f: &mut Formatter)->fmt::Result{
+		let keys=[(JoypadState::LEFT,"<"),(JoypadState::UP,"^"),(JoypadState::DOWN,"v"),
+			(JoypadState::RIGHT,">"),(JoypadState::B," B"),(JoypadState::A," A"),
+			(JoypadState::SELECT," SELECT"),(JoypadState::START," START")];
+		for &(key,label) in keys.iter(){
+			if self.contains(key) {
+				f.write_str(label)?;
+			}
+			else {
+				for c in label.chars(){
+					f.write_str(if c==' ' {" "} else {"."})?;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+impl BitOr for JoypadState{
+	type Output=JoypadState;
+
+	fn bitor(self,keys: JoypadState)->JoypadState{
+		JoypadState(self.0|keys.0)
+	}
+}
+
+impl BitOrAssign for JoypadState{
+	fn bitor_assign(&mut self,keys: JoypadState){
+		self.insert(keys);
+	}
+}
+
+#[derive(Debug)]
+pub struct Joypad{
+	//bits 4 and 5 as last written.
+	select: u8,
+	state: JoypadState,
+	interrupt: bool,
+}
+
+impl Default for Joypad{
+	fn default()->Self{
+		Joypad::new()
+	}
+}
+
+impl Joypad{
+	pub fn new()->Self{
+		Joypad{select: SELECT_DIRECTIONS|SELECT_BUTTONS,state: JoypadState::default(),interrupt: false}
+	}
+
+	pub fn state(&self)->JoypadState{
+		self.state
+	}
+
+	pub fn set_state(&mut self,state: JoypadState){
+		let before=self.lines();
+		self.state=state;
+		self.check_interrupt(before);
+	}
+
+	//single key changes, for debuggers poking at the keys by hand.
+	pub fn press(&mut self,keys: JoypadState){
+		let mut state=self.state;
+		state.insert(keys);
+		self.set_state(state);
+	}
+
+	pub fn release(&mut self,keys: JoypadState){
+		let mut state=self.state;
+		state.remove(keys);
+		self.set_state(state);
+	}
+
+	//low nibble of P1, 1 for every key held on a selected line.
+	fn lines(&self)->u8{
+		let mut lines=0;
+		if self.select&SELECT_DIRECTIONS==0 {
+			lines|=self.state.0>>4;
+		}
+		if self.select&SELECT_BUTTONS==0 {
+			lines|=self.state.0&0x0F;
+		}
+		lines
+	}
+
+	//the interrupt fires when a line goes from high (released) to low.
+	fn check_interrupt(&mut self,before: u8){
+		if self.lines()&!before!=0 {
+			self.interrupt=true;
+		}
+	}
+}
+
+impl Peripheral for Joypad{
+	fn handles(&self,address: u16)->bool{
+		address==P1
+	}
+
+	fn read(&self,_address: u16)->u8{
+		0xC0|self.select|(!self.lines()&0x0F)
+	}
+
+	fn write(&mut self,_address: u16,data: u8){
+		let before=self.lines();
+		self.select=data&(SELECT_DIRECTIONS|SELECT_BUTTONS);
+		self.check_interrupt(before);
+	}
+
+	fn pending_interrupt(&mut self)->u8{
+		if ::std::mem::take(&mut self.interrupt) {interrupt::JOYPAD} else {0}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{Joypad,JoypadState};
+	use gb::peripheral::Peripheral;
+	use gb::interrupt;
+
+	#[test]
+	fn selected_lines_read_low(){
+		let mut joypad=Joypad::new();
+		joypad.set_state(JoypadState::A|JoypadState::DOWN);
+		assert_eq!(joypad.read(0xFF00),0xFF);
+		joypad.write(0xFF00,0x20);
+		assert_eq!(joypad.read(0xFF00),0xE7);
+		joypad.write(0xFF00,0x10);
+		assert_eq!(joypad.read(0xFF00),0xDE);
+	}
+
+	#[test]
+	fn pressing_a_selected_key_interrupts(){
+		let mut joypad=Joypad::new();
+		joypad.set_state(JoypadState::START);
+		assert_eq!(joypad.pending_interrupt(),0);
+		//selecting a line with a key already held counts as it going low
+		joypad.write(0xFF00,0x10);
+		assert_eq!(joypad.pending_interrupt(),interrupt::JOYPAD);
+		joypad.set_state(JoypadState::START);
+		assert_eq!(joypad.pending_interrupt(),0);
+		joypad.set_state(JoypadState::LEFT);
+		assert_eq!(joypad.pending_interrupt(),0);
+		joypad.write(0xFF00,0x20);
+		assert_eq!(joypad.pending_interrupt(),interrupt::JOYPAD);
+		joypad.set_state(JoypadState::default());
+		assert_eq!(joypad.pending_interrupt(),0);
+		joypad.set_state(JoypadState::LEFT);
+		assert_eq!(joypad.pending_interrupt(),interrupt::JOYPAD);
+	}
+
+	#[test]
+	fn press_and_release_keep_other_keys(){
+		let mut joypad=Joypad::new();
+		joypad.set_state(JoypadState::B);
+		joypad.press(JoypadState::UP|JoypadState::A);
+		assert_eq!(joypad.state(),JoypadState::from_bits(0x43));
+		joypad.release(JoypadState::A);
+		assert!(joypad.state().contains(JoypadState::B|JoypadState::UP));
+		assert!(!joypad.state().contains(JoypadState::A));
+	}
+
+	#[test]
+	fn input_display(){
+		assert_eq!((JoypadState::UP|JoypadState::A|JoypadState::START).to_string(),".^.. . A ...... START");
+		assert_eq!(JoypadState::default().to_string().len(),21);
+	}
+}
@@ -0,0 +1,192 @@
+//Link cable over TCP, to play with an emulator on another machine.
+//
+//Every message is a u16 (be) length followed by the payload, whose first
+//byte is the message kind. Both ends start by sending HELLO with the
+//protocol version and their serial clock, and refuse to link if they
+//don't agree. After that the side that starts a transfer (internal clock)
+//sends DATA and waits for REPLY.
+//
+//To hide latency DATA is answered right away by a reader thread with the
+//last SB value the emulation published, instead of waiting for the other
+//emulator to get around to it. A peer that stops replying altogether
+//reads as a lost connection after REPLY_TIMEOUT rather than hanging.
+use gb::serial::SerialDevice;
+use std::io::{self,Read,Write};
+use std::net::{TcpListener,TcpStream,ToSocketAddrs};
+use std::sync::{Arc,Mutex};
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::sync::mpsc::{self,Receiver,Sender};
+use std::thread;
+use std::time::Duration;
+
+const VERSION: u8 = 1;
+//bits per second on the DMG.
+const CLOCK: u32 = 8192;
+//a byte takes 1ms to shift out, anything this late isn't coming.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+
+const HELLO: u8 = 0;
+const DATA: u8 = 1;
+const REPLY: u8 = 2;
+
+pub struct TcpLink{
+	writer: Arc<Mutex<TcpStream>>,
+	//SB as last seen by the emulation, answered to DATA.
+	latched: Arc<AtomicUsize>,
+	replies: Receiver<u8>,
+	incoming: Receiver<u8>,
+	connected: bool,
+}
+
+impl TcpLink{
+	pub fn listen(port: u16)->io::Result<Self>{
+		let listener=TcpListener::bind(("0.0.0.0",port))?;
+		println!("Link: waiting for the other player on port {}",port);
+		let (stream,peer)=listener.accept()?;
+		println!("Link: connected to {}",peer);
+		TcpLink::start(stream)
+	}
+
+	pub fn connect<A: ToSocketAddrs>(address: A)->io::Result<Self>{
+		let stream=TcpStream::connect(address)?;
+		println!("Link: connected to {}",stream.peer_addr()?);
+		TcpLink::start(stream)
+	}
+
+	fn start(mut stream: TcpStream)->io::Result<Self>{
+		stream.set_nodelay(true)?;
+		let clock=[(CLOCK>>24) as u8,(CLOCK>>16) as u8,(CLOCK>>8) as u8,CLOCK as u8];
+		send(&mut stream,&[HELLO,VERSION,clock[0],clock[1],clock[2],clock[3]])?;
+		let hello=receive(&mut stream)?;
+		if hello.len()!=6 || hello[0]!=HELLO {
+			return Err(io::Error::new(io::ErrorKind::InvalidData,"other end didn't say hello"));
+		}
+		if hello[1]!=VERSION || hello[2..]!=clock {
+			return Err(io::Error::new(io::ErrorKind::InvalidData,
+				format!("other end speaks version {} at a different clock",hello[1])));
+		}
+
+		let writer=Arc::new(Mutex::new(stream.try_clone()?));
+		let latched=Arc::new(AtomicUsize::new(0xFF));
+		let (reply_tx,replies)=mpsc::channel();
+		let (incoming_tx,incoming)=mpsc::channel();
+		{
+			let writer=writer.clone();
+			let latched=latched.clone();
+			thread::spawn(move || read_loop(stream,writer,latched,reply_tx,incoming_tx));
+		}
+		Ok(TcpLink{writer,latched,replies,incoming,connected: true})
+	}
+
+	fn disconnected(&mut self)->u8{
+		if self.connected {
+			eprintln!("Link: connection lost");
+			self.connected=false;
+		}
+		0xFF
+	}
+}
+
+impl SerialDevice for TcpLink{
+	fn exchange(&mut self,byte: u8)->u8{
+		//replies that came in after we gave up on them
+		while self.replies.try_recv().is_ok() {}
+		let sent=send(&mut self.writer.lock().unwrap(),&[DATA,byte]);
+		match sent.ok().and_then(|_| self.replies.recv_timeout(REPLY_TIMEOUT).ok()){
+			Some(reply) => reply,
+			None        => self.disconnected(),
+		}
+	}
+
+	fn external_clock(&mut self,sb: u8)->Option<u8>{
+		self.latched.store(sb as usize,Ordering::SeqCst);
+		self.incoming.try_recv().ok()
+	}
+}
+
+fn read_loop(mut stream: TcpStream,writer: Arc<Mutex<TcpStream>>,latched: Arc<AtomicUsize>,
+		replies: Sender<u8>,incoming: Sender<u8>){
+	while let Ok(message)=receive(&mut stream){
+		let delivered=match (message.first(),message.get(1)){
+			(Some(&DATA),Some(&byte)) => {
+				let reply=latched.load(Ordering::SeqCst) as u8;
+				//nothing more gets shifted out until the game reloads SB
+				latched.store(0xFF,Ordering::SeqCst);
+				send(&mut writer.lock().unwrap(),&[REPLY,reply]).is_ok() &&
+					incoming.send(byte).is_ok()
+			},
+			(Some(&REPLY),Some(&byte)) => replies.send(byte).is_ok(),
+			_                          => true,
+		};
+		if !delivered {
+			break;
+		}
+	}
+}
+
+fn send(stream: &mut TcpStream,payload: &[u8])->io::Result<()>{
+	let len=payload.len() as u16;
+	stream.write_all(&[(len>>8) as u8,len as u8])?;
+	stream.write_all(payload)
+}
+
+fn receive(stream: &mut TcpStream)->io::Result<Vec<u8>>{
+	let mut len=[0;2];
+	stream.read_exact(&mut len)?;
+	let mut payload=vec![0;(len[0] as usize)<<8 | len[1] as usize];
+	stream.read_exact(&mut payload)?;
+	Ok(payload)
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{TcpLink,send,receive,HELLO,DATA,VERSION,CLOCK};
+	use gb::serial::SerialDevice;
+	use std::net::TcpListener;
+	use std::sync::mpsc;
+	use std::thread;
+
+	#[test]
+	fn exchange_over_tcp(){
+		let port=TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+		let listener=thread::spawn(move || TcpLink::listen(port).unwrap());
+		let mut master=loop{
+			if let Ok(link)=TcpLink::connect(("127.0.0.1",port)) {
+				break link;
+			}
+			thread::yield_now();
+		};
+		let mut slave=listener.join().unwrap();
+		//the slave publishes its SB while it waits for our clock
+		assert_eq!(slave.external_clock(0x42),None);
+		assert_eq!(master.exchange(0x24),0x42);
+		let received=loop{
+			if let Some(byte)=slave.external_clock(0x00) {
+				break byte;
+			}
+			thread::yield_now();
+		};
+		assert_eq!(received,0x24);
+	}
+
+	#[test]
+	fn silent_peer_times_out(){
+		let listener=TcpListener::bind("127.0.0.1:0").unwrap();
+		let port=listener.local_addr().unwrap().port();
+		let (done,wait)=mpsc::channel::<()>();
+		//says hello, then never answers
+		let peer=thread::spawn(move || {
+			let (mut stream,_)=listener.accept().unwrap();
+			let clock=CLOCK.to_be_bytes();
+			send(&mut stream,&[HELLO,VERSION,clock[0],clock[1],clock[2],clock[3]]).unwrap();
+			receive(&mut stream).unwrap();
+			assert_eq!(receive(&mut stream).unwrap(),vec![DATA,0x24]);
+			let _=wait.recv();
+		});
+		let mut link=TcpLink::connect(("127.0.0.1",port)).unwrap();
+		assert_eq!(link.exchange(0x24),0xFF);
+		drop(done);
+		peer.join().unwrap();
+	}
+}
@@ -0,0 +1,64 @@
+//Runs two differently configured GameBoys side by side and compares their
+//state after every frame, to check that a configuration (instrumentation,
+//an optimization...) doesn't change what the game sees.
+use gb::gameboy::GameBoy;
+
+use std::fmt;
+
+#[derive(Debug,PartialEq)]
+pub struct Divergence{
+	pub frame: u64,
+	pub checksums: (u64,u64),
+}
+
+impl fmt::Display for Divergence{
+	fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f,"frame {}: state checksums differ ({:016X} vs {:016X})",
+			self.frame,self.checksums.0,self.checksums.1)
+	}
+}
+
+//steps both instances frame by frame and stops at the first frame after
+//which their states don't match.
+pub fn run(a: &mut GameBoy,b: &mut GameBoy,frames: u64) -> Result<(),Divergence> {
+	for _ in 0..frames{
+		let frame=a.frame();
+		a.step_frame();
+		b.step_frame();
+		let checksums=(a.state_checksum(),b.state_checksum());
+		if checksums.0!=checksums.1 {
+			return Err(Divergence{frame,checksums});
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::run;
+	use gb::gameboy::GameBoy;
+	use gb::interconnect::RamInit;
+	use gb::testrom;
+
+	fn instance(ram_init: RamInit) -> GameBoy {
+		let mut gb=GameBoy::new(testrom::tiny_rom(),ram_init);
+		gb.set_verbose(false);
+		gb
+	}
+
+	#[test]
+	fn instrumentation_does_not_diverge(){
+		let mut a=instance(RamInit::Zero);
+		let mut b=instance(RamInit::Zero);
+		b.check_timing();
+		assert_eq!(run(&mut a,&mut b,3),Ok(()));
+	}
+
+	#[test]
+	fn different_ram_diverges(){
+		let mut a=instance(RamInit::Zero);
+		let mut b=instance(RamInit::Ones);
+		assert_eq!(run(&mut a,&mut b,3).map_err(|d| d.frame),Err(0));
+	}
+}
@@ -0,0 +1,55 @@
+//Tracks a memory range between two points in time and reports only the
+//bytes that changed, easier to follow than whole hexdumps.
+
+pub struct MemoryDiff{
+	start: u16,
+	end: u16,
+	previous: Vec<u8>,
+}
+
+impl MemoryDiff{
+	//`peek` gives the contents the range starts with.
+	pub fn new(start: u16,end: u16,peek: &dyn Fn(u16)->u8)->Self{
+		MemoryDiff{start,end,previous: (start..=end).map(peek).collect()}
+	}
+
+	//(address, old, new) for every byte that changed since the last call.
+	pub fn update(&mut self,peek: &dyn Fn(u16)->u8)->Vec<(u16,u8,u8)>{
+		let mut changes=Vec::new();
+		for (address,old) in (self.start..=self.end).zip(self.previous.iter_mut()){
+			let new=peek(address);
+			if new!=*old {
+				changes.push((address,*old,new));
+				*old=new;
+			}
+		}
+		changes
+	}
+}
+
+pub fn format_changes(changes: &[(u16,u8,u8)])->String{
+	changes.iter()
+		.map(|&(address,old,new)| format!("{:04X}:{:02X}->{:02X}",address,old,new))
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{MemoryDiff,format_changes};
+	use std::cell::RefCell;
+
+	#[test]
+	fn only_changes_are_reported(){
+		let memory=RefCell::new(vec![0u8;0x10]);
+		let peek=|a: u16| memory.borrow()[a as usize];
+		let mut diff=MemoryDiff::new(0x04,0x07,&peek);
+		assert!(diff.update(&peek).is_empty());
+		memory.borrow_mut()[0x05]=0x12;
+		memory.borrow_mut()[0x08]=0x34;
+		let changes=diff.update(&peek);
+		assert_eq!(format_changes(&changes),"0005:00->12");
+		assert!(diff.update(&peek).is_empty());
+	}
+}
@@ -0,0 +1,171 @@
+//The DMG memory map as data. Interconnect dispatches through it and
+//tooling can use it to describe addresses.
+//
+//| Range       | Region       | Device    | R | W | Notes                   |
+//|-------------|--------------|-----------|---|---|-------------------------|
+//| 0000-7FFF   | ROM          | Cartridge | y | y | writes go to the MBC    |
+//| 8000-9FFF   | VRAM         | Vram      | y | y |                         |
+//| A000-BFFF   | External RAM | Cartridge | y | y |                         |
+//| C000-DFFF   | WRAM         | Wram      | y | y |                         |
+//| E000-FDFF   | Echo RAM     | Wram      | y | y | mirror of C000-DDFF     |
+//| FE00-FE9F   | OAM          | Oam       | y | y |                         |
+//| FEA0-FEFF   | Unusable     | Unusable  | n | n | reads 0xFF, writes lost |
+//| FF00-FF7F   | IO           | Io        | y | y |                         |
+//| FF80-FFFE   | HRAM         | Hram      | y | y |                         |
+//| FFFF        | IE           | Interrupt | y | y |                         |
+//
+//Regions that aren't readable read as 0xFF and writes to regions that
+//aren't writable are ignored.
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Device{
+	Cartridge,
+	Vram,
+	Wram,
+	Oam,
+	Unusable,
+	Io,
+	Hram,
+	Interrupt,
+}
+
+#[derive(Debug)]
+pub struct Region{
+	pub start: u16,
+	pub end: u16,
+	pub name: &'static str,
+	pub device: Device,
+	pub readable: bool,
+	pub writable: bool,
+	//start of the region this one mirrors.
+	pub mirror_of: Option<u16>,
+}
+
+impl Region{
+	//offset of `address` into the device backing this region.
+	pub fn offset(&self,address: u16)->usize{
+		(address-self.start) as usize
+	}
+}
+
+macro_rules! region{
+	($start:expr,$end:expr,$name:expr,$device:ident,$readable:expr,$writable:expr,$mirror:expr) => {
+		Region{start: $start,end: $end,name: $name,device: Device::$device,
+			readable: $readable,writable: $writable,mirror_of: $mirror}
+	}
+}
+
+pub const MEMORY_MAP: [Region;10] = [
+	region!(0x0000,0x7FFF,"ROM",Cartridge,true,true,None),
+	region!(0x8000,0x9FFF,"VRAM",Vram,true,true,None),
+	region!(0xA000,0xBFFF,"External RAM",Cartridge,true,true,None),
+	region!(0xC000,0xDFFF,"WRAM",Wram,true,true,None),
+	region!(0xE000,0xFDFF,"Echo RAM",Wram,true,true,Some(0xC000)),
+	region!(0xFE00,0xFE9F,"OAM",Oam,true,true,None),
+	region!(0xFEA0,0xFEFF,"Unusable",Unusable,false,false,None),
+	region!(0xFF00,0xFF7F,"IO",Io,true,true,None),
+	region!(0xFF80,0xFFFE,"HRAM",Hram,true,true,None),
+	region!(0xFFFF,0xFFFF,"IE",Interrupt,true,true,None),
+];
+
+pub fn region(address: u16)->&'static Region{
+	MEMORY_MAP.iter()
+		.find(|r| address>=r.start && address<=r.end)
+		.expect("memory map doesn't cover every address")
+}
+
+//how a dump shows what's in memory. Game variables are often 16 bit or
+//BCD (scores), tedious to decode from bytes by hand.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum DumpFormat{
+	Bytes,
+	//little endian, from even offsets of each line.
+	Words,
+	//bytes as i8.
+	Signed,
+	//two decimal digits a byte, '?' for nibbles over 9.
+	Bcd,
+}
+
+impl DumpFormat{
+	pub fn from_name(name: &str)->Option<DumpFormat>{
+		match name{
+			"bytes"  => Some(DumpFormat::Bytes),
+			"words"  => Some(DumpFormat::Words),
+			"signed" => Some(DumpFormat::Signed),
+			"bcd"    => Some(DumpFormat::Bcd),
+			_        => None,
+		}
+	}
+
+	//bytes shown per cell.
+	fn size(self)->u32{
+		if self==DumpFormat::Words {2} else {1}
+	}
+
+	//characters per cell, leaving out the separator.
+	fn width(self)->usize{
+		match self{
+			DumpFormat::Bytes | DumpFormat::Bcd => 2,
+			DumpFormat::Words | DumpFormat::Signed => 4,
+		}
+	}
+
+	fn cell(self,bytes: &[u8])->String{
+		match self{
+			DumpFormat::Bytes  => format!("{:02X}",bytes[0]),
+			DumpFormat::Words  => format!("{:04X}",bytes[0] as u16|(bytes[1] as u16)<<8),
+			DumpFormat::Signed => format!("{:>4}",bytes[0] as i8),
+			DumpFormat::Bcd    => [bytes[0]>>4,bytes[0]&0xF].iter()
+				.map(|&digit| if digit<10 {(b'0'+digit) as char} else {'?'})
+				.collect(),
+		}
+	}
+}
+
+//Hexdump of start..=end, 16 bytes a line. Bytes in a mirror are marked
+//with '~' instead of a space and bytes nothing answers for (`peek` gives
+//None) print as "--", so neither passes for real data. Each line ends with
+//the regions it covers.
+pub fn format_memory_dump(start: u16,end: u16,peek: &dyn Fn(u16)->Option<u8>)->Vec<String>{
+	format_memory_dump_as(start,end,DumpFormat::Bytes,peek)
+}
+
+//format_memory_dump showing cells as `format`. Words sticking out of
+//start..=end are left blank.
+pub fn format_memory_dump_as(start: u16,end: u16,format: DumpFormat,peek: &dyn Fn(u16)->Option<u8>)->Vec<String>{
+	let mut lines=Vec::new();
+	let mut line_start=start as u32&!0xF;
+	while line_start<=end as u32 {
+		let mut cells=String::new();
+		let mut names: Vec<&str>=Vec::new();
+		for cell in (line_start..line_start+16).step_by(format.size() as usize) {
+			let addresses=cell..cell+format.size();
+			if addresses.start<start as u32 || addresses.end-1>end as u32 {
+				cells.push_str(&" ".repeat(format.width()+1));
+				continue;
+			}
+			let mut mirror=false;
+			let mut bytes=Vec::new();
+			for address in addresses{
+				let region=region(address as u16);
+				if !names.contains(&region.name) {
+					names.push(region.name);
+				}
+				mirror|=region.mirror_of.is_some();
+				bytes.extend(peek(address as u16));
+			}
+			let separator=if mirror {'~'} else {' '};
+			if bytes.len()==format.size() as usize {
+				cells.push_str(&format!("{}{}",separator,format.cell(&bytes)));
+			}
+			else {
+				cells.push_str(&format!(" {}","-".repeat(format.width())));
+			}
+		}
+		lines.push(format!("{:04X} |{} | {}",line_start,cells,names.join(", ")));
+		line_start+=16;
+	}
+	lines
+}
+
@@ -1,7 +1,45 @@
 //sort this thing out 
 pub mod cpu;
 pub mod opcode;
+pub mod isa;
 pub mod interconnect;
 pub mod gameboy;
 pub mod register;
-pub mod cartridge;
\ No newline at end of file
+pub mod cartridge;
+pub mod compat;
+pub mod frontend;
+pub mod trace;
+pub mod video;
+pub mod serial;
+pub mod printer;
+pub mod link;
+pub mod memory_map;
+pub mod save;
+pub mod snapshot;
+pub mod coverage;
+pub mod rtc;
+pub mod rumble;
+pub mod testrom;
+pub mod hooks;
+pub mod timing;
+pub mod selftest;
+pub mod sram_watch;
+pub mod peripheral;
+pub mod scheduler;
+pub mod checksum;
+pub mod interrupt;
+pub mod regwatch;
+pub mod rgbds_map;
+pub mod memdiff;
+pub mod history;
+pub mod breakpoints;
+pub mod sprite_debug;
+pub mod lockstep;
+pub mod pacer;
+pub mod joypad;
+pub mod apu;
+pub mod io_registers;
+pub mod instances;
+#[cfg(feature="async")]
+pub mod async_gameboy;
+pub mod png;
\ No newline at end of file
@@ -1,20 +1,69 @@
-enum_from_primitive!{
-	#[derive(Debug)]
-	pub enum Opcode{
-		////// 0x0X
-		Nop = 0x00,
-		Ld_Bc_d16 = 0x01,
-		Dec_Bc = 0x0B,
-		////// 0x1X
-		////// 0x2X
-		Dec_H = 0x25,
-		Cpl = 0x2F,
-		////// 0x3X
-		//////0x4X
-		Ld_C_B = 0x48,
-		Ld_C_C = 0x49,
-		Ld_C_D = 0x4A,
-		//////
-		Jp_a16=0xC3,
+//Declares the Opcode enum together with its decoder and cycle counts so
+//they can't drift apart. Conditional instructions list the cycles when the
+//condition fails first and when it holds after a `;`.
+macro_rules! opcodes{
+	($($name:ident = $value:expr => $cycles:expr $(; $taken:expr)*,)*) => {
+		#[derive(Debug,Clone,Copy,PartialEq)]
+		pub enum Opcode{
+			$($name = $value,)*
+		}
+
+		impl Opcode{
+			pub fn decode(byte: u8)->Option<Opcode>{
+				match byte{
+					$($value => Some(Opcode::$name),)*
+					_        => None,
+				}
+			}
+
+			//cycles taken, and for conditional instructions the cycles
+			//taken when the branch is taken.
+			pub fn cycles(self)->(usize,Option<usize>){
+				match self{
+					$(Opcode::$name => ($cycles,None$(.or(Some($taken)))*),)*
+				}
+			}
+		}
+	}
+}
+
+opcodes!{
+	////// 0x0X
+	Nop = 0x00 => 4,
+	LdBcD16 = 0x01 => 12,
+	DecBc = 0x0B => 8,
+	////// 0x1X
+	////// 0x2X
+	DecH = 0x25 => 4,
+	Cpl = 0x2F => 4,
+	////// 0x3X
+	//////0x4X
+	LdCB = 0x48 => 4,
+	LdCC = 0x49 => 4,
+	LdCD = 0x4A => 4,
+	//////
+	JpA16 = 0xC3 => 16,
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::Opcode;
+
+	#[test]
+	fn decode_round_trip(){
+		for byte in 0..=255u8{
+			if let Some(op)=Opcode::decode(byte) {
+				assert_eq!(op as u8,byte);
+			}
+		}
+		assert_eq!(Opcode::decode(0xC3),Some(Opcode::JpA16));
+		assert_eq!(Opcode::decode(0xD3),None);
+	}
+
+	#[test]
+	fn cycles(){
+		assert_eq!(Opcode::Nop.cycles(),(4,None));
+		assert_eq!(Opcode::JpA16.cycles(),(16,None));
 	}
 }
@@ -0,0 +1,75 @@
+//Keeps emulation at the speed of real hardware. Sleeps are coarse (a few
+//ms on some OSes), so the pacer sleeps for most of the wait and spins for
+//the rest, and schedules every frame from the previous deadline rather
+//than from when it woke up so rounding errors don't add up into drift.
+use gb::gameboy::CYCLES_PER_FRAME;
+
+use std::hint;
+use std::thread;
+use std::time::{Duration,Instant};
+
+//59.73Hz.
+pub const FRAME_RATE: f64 = 4_194_304.0/CYCLES_PER_FRAME as f64;
+
+//left to spin after sleeping, covers the sleep overshooting.
+const SPIN: Duration = Duration::from_millis(2);
+//more frames late than this (paused in a debugger, host too slow) and the
+//pacer starts over instead of running flat out to catch up.
+const MAX_FRAMES_BEHIND: u32 = 4;
+
+#[derive(Debug)]
+pub struct FramePacer{
+	interval: Duration,
+	deadline: Option<Instant>,
+}
+
+impl FramePacer{
+	pub fn new(rate: f64)->Self{
+		FramePacer{interval: Duration::from_secs_f64(1.0/rate),deadline: None}
+	}
+
+	//blocks until the next frame is due.
+	pub fn wait(&mut self){
+		if let Some(deadline)=self.schedule(Instant::now()) {
+			if let Some(sleep)=deadline.checked_duration_since(Instant::now()+SPIN) {
+				thread::sleep(sleep);
+			}
+			while Instant::now()<deadline{
+				hint::spin_loop();
+			}
+		}
+	}
+
+	//when the frame starting at `now` has to wait until, if at all.
+	fn schedule(&mut self,now: Instant)->Option<Instant>{
+		let deadline=match self.deadline{
+			Some(deadline) if now<deadline+self.interval*MAX_FRAMES_BEHIND => deadline,
+			_ => now,
+		};
+		self.deadline=Some(deadline+self.interval);
+		if now<deadline {Some(deadline)} else {None}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::FramePacer;
+	use std::time::{Duration,Instant};
+
+	#[test]
+	fn deadlines_do_not_drift(){
+		let ms=Duration::from_millis;
+		let start=Instant::now();
+		let mut pacer=FramePacer::new(100.0);
+		assert_eq!(pacer.schedule(start),None);
+		//early: wait for the deadline
+		assert_eq!(pacer.schedule(start+ms(3)),Some(start+ms(10)));
+		//woke up late, the next frame is still due on the 10ms grid
+		assert_eq!(pacer.schedule(start+ms(22)),None);
+		assert_eq!(pacer.schedule(start+ms(25)),Some(start+ms(30)));
+		//far behind: start over from now
+		assert_eq!(pacer.schedule(start+ms(500)),None);
+		assert_eq!(pacer.schedule(start+ms(501)),Some(start+ms(510)));
+	}
+}
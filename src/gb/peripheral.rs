@@ -0,0 +1,21 @@
+//Hardware hanging off the IO registers. Interconnect keeps a list of
+//them, steps them all after every instruction and routes IO accesses to
+//whichever one claims the address, so a new one only has to be added to
+//that list.
+
+pub trait Peripheral{
+	//whether `address` is one of its registers.
+	fn handles(&self,address: u16)->bool;
+
+	fn step(&mut self,_cycles: u32){}
+
+	//reads must not have side effects, tooling peeks through them.
+	fn read(&self,address: u16)->u8;
+
+	fn write(&mut self,address: u16,data: u8);
+
+	//IF bits raised since the last call, which clears them.
+	fn pending_interrupt(&mut self)->u8{
+		0
+	}
+}
@@ -0,0 +1,115 @@
+//Just enough of a PNG encoder to save images: 8 bit grayscale with the
+//image stored in uncompressed deflate blocks.
+
+pub fn encode_grayscale(width: u32,height: u32,pixels: &[u8])->Vec<u8>{
+	assert_eq!(pixels.len(),(width*height) as usize);
+	//grayscale
+	encode(width,height,0,1,pixels)
+}
+
+//8 bits a channel.
+pub fn encode_rgb(width: u32,height: u32,pixels: &[[u8;3]])->Vec<u8>{
+	assert_eq!(pixels.len(),(width*height) as usize);
+	let bytes: Vec<u8>=pixels.iter().flat_map(|pixel| pixel.iter().cloned()).collect();
+	//truecolor
+	encode(width,height,2,3,&bytes)
+}
+
+fn encode(width: u32,height: u32,color_type: u8,channels: usize,bytes: &[u8])->Vec<u8>{
+	let mut raw=Vec::with_capacity(bytes.len()+height as usize);
+	for row in bytes.chunks(width as usize*channels){
+		//filter type none
+		raw.push(0);
+		raw.extend_from_slice(row);
+	}
+
+	let mut header=Vec::new();
+	header.extend_from_slice(&be32(width));
+	header.extend_from_slice(&be32(height));
+	//bit depth 8, deflate, no filter, no interlace
+	header.extend_from_slice(&[8,color_type,0,0,0]);
+
+	let mut png=vec![0x89,b'P',b'N',b'G',0x0D,0x0A,0x1A,0x0A];
+	chunk(&mut png,b"IHDR",&header);
+	chunk(&mut png,b"IDAT",&zlib_stored(&raw));
+	chunk(&mut png,b"IEND",&[]);
+	png
+}
+
+fn chunk(png: &mut Vec<u8>,kind: &[u8;4],data: &[u8]){
+	png.extend_from_slice(&be32(data.len() as u32));
+	let start=png.len();
+	png.extend_from_slice(kind);
+	png.extend_from_slice(data);
+	let crc=crc32(&png[start..]);
+	png.extend_from_slice(&be32(crc));
+}
+
+fn zlib_stored(data: &[u8])->Vec<u8>{
+	let mut out=vec![0x78,0x01];
+	let mut blocks=data.chunks(0xFFFF).peekable();
+	if blocks.peek().is_none() {
+		out.extend_from_slice(&[1,0,0,0xFF,0xFF]);
+	}
+	while let Some(block)=blocks.next(){
+		let last=blocks.peek().is_none();
+		let len=block.len() as u16;
+		out.push(last as u8);
+		out.extend_from_slice(&[len as u8,(len>>8) as u8,!len as u8,(!len>>8) as u8]);
+		out.extend_from_slice(block);
+	}
+	out.extend_from_slice(&be32(adler32(data)));
+	out
+}
+
+fn crc32(data: &[u8])->u32{
+	let mut crc=0xFFFFFFFFu32;
+	for &b in data{
+		crc^=b as u32;
+		for _ in 0..8{
+			crc=if crc&1!=0 {(crc>>1)^0xEDB88320} else {crc>>1};
+		}
+	}
+	!crc
+}
+
+fn adler32(data: &[u8])->u32{
+	let (mut a,mut b)=(1u32,0u32);
+	for &byte in data{
+		a=(a+byte as u32)%65521;
+		b=(b+a)%65521;
+	}
+	b<<16 | a
+}
+
+fn be32(n: u32)->[u8;4]{
+	[(n>>24) as u8,(n>>16) as u8,(n>>8) as u8,n as u8]
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{crc32,encode_grayscale,encode_rgb};
+
+	#[test]
+	fn known_crc(){
+		assert_eq!(crc32(b"IEND"),0xAE426082);
+	}
+
+	#[test]
+	fn header(){
+		let png=encode_grayscale(2,1,&[0,255]);
+		assert_eq!(&png[..8],&[0x89,b'P',b'N',b'G',0x0D,0x0A,0x1A,0x0A]);
+		assert_eq!(&png[12..16],b"IHDR");
+		assert_eq!(&png[png.len()-12..],&[0,0,0,0,b'I',b'E',b'N',b'D',0xAE,0x42,0x60,0x82]);
+	}
+
+	#[test]
+	fn rgb_rows(){
+		let png=encode_rgb(2,1,&[[1,2,3],[4,5,6]]);
+		//color type
+		assert_eq!(png[25],2);
+		//stored block after the zlib and block headers, led by the filter byte
+		assert_eq!(&png[41+7..41+7+7],&[0,1,2,3,4,5,6]);
+	}
+}
@@ -0,0 +1,212 @@
+//Game Boy Printer, plugged into the serial port. Decodes print packets and
+//saves every printed image as a PNG.
+//
+//A packet is: 0x88 0x33, command, compression, length (le), data,
+//checksum (le), then two bytes where the printer answers 0x81 and its
+//status.
+use gb::serial::SerialDevice;
+use gb::video::TileData;
+use gb::png;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+const COMMAND_INIT: u8 = 0x01;
+const COMMAND_PRINT: u8 = 0x02;
+const COMMAND_DATA: u8 = 0x04;
+
+const STATUS_CHECKSUM_ERROR: u8 = 0x01;
+const STATUS_PRINTING: u8 = 0x02;
+const STATUS_UNPROCESSED: u8 = 0x08;
+
+const WIDTH: usize = 160;
+//tiles per row of the printed image
+const TILES_PER_ROW: usize = WIDTH/8;
+
+pub struct Printer{
+	packet: Vec<u8>,
+	//2bpp tile data received since the last print, 20 tiles per row.
+	image: Vec<u8>,
+	status: u8,
+	output_dir: PathBuf,
+	printed: usize,
+}
+
+impl Printer{
+	pub fn new(output_dir: PathBuf)->Self{
+		Printer{packet: Vec::new(),image: Vec::new(),status: 0,output_dir,printed: 0}
+	}
+
+	fn data_length(&self)->usize{
+		self.packet[4] as usize | (self.packet[5] as usize)<<8
+	}
+
+	fn process_packet(&mut self){
+		let length=self.data_length();
+		let body=&self.packet[2..6+length];
+		let checksum=body.iter().fold(0u16,|sum,&b| sum.wrapping_add(b as u16));
+		let expected=self.packet[6+length] as u16 | (self.packet[7+length] as u16)<<8;
+		if checksum!=expected {
+			self.status|=STATUS_CHECKSUM_ERROR;
+			return;
+		}
+		self.status&=!STATUS_CHECKSUM_ERROR;
+		let data=self.packet[6..6+length].to_vec();
+		match self.packet[2]{
+			COMMAND_INIT  => {
+				self.image.clear();
+				self.status=0;
+			},
+			COMMAND_DATA  => {
+				if self.packet[3]&1!=0 {
+					decompress(&data,&mut self.image);
+				}
+				else {
+					self.image.extend_from_slice(&data);
+				}
+				self.status|=STATUS_UNPROCESSED;
+			},
+			COMMAND_PRINT => {
+				//a zero palette means the default one
+				let palette=match data.get(2){
+					Some(&0) | None => 0xE4,
+					Some(&p)        => p,
+				};
+				self.print(palette);
+				self.image.clear();
+				self.status=(self.status&!STATUS_UNPROCESSED)|STATUS_PRINTING;
+			},
+			//0x0F asks for the status, anything else is ignored
+			_ => {},
+		}
+	}
+
+	fn print(&mut self,palette: u8){
+		let pixels=render(&self.image,palette);
+		if pixels.is_empty() {
+			return;
+		}
+		let path=self.output_dir.join(format!("print_{:03}.png",self.printed));
+		self.printed+=1;
+		let png=png::encode_grayscale(WIDTH as u32,(pixels.len()/WIDTH) as u32,&pixels);
+		match File::create(&path).and_then(|mut f| f.write_all(&png)){
+			Ok(())   => println!("Printer: saved {}",path.display()),
+			Err(e)   => eprintln!("Printer: cannot save {}: {}",path.display(),e),
+		}
+	}
+}
+
+impl SerialDevice for Printer{
+	fn exchange(&mut self,byte: u8)->u8{
+		//wait for the magic bytes before buffering anything
+		match (self.packet.len(),byte){
+			(0,0x88) | (1,0x33) => {},
+			(0,_)               => return 0x00,
+			(1,_)               => {
+				self.packet.clear();
+				return 0x00;
+			},
+			_                   => {},
+		}
+		self.packet.push(byte);
+		if self.packet.len()<6 {
+			return 0x00;
+		}
+		//header, data and checksum
+		let end=8+self.data_length();
+		if self.packet.len()<=end {
+			0x00
+		}
+		else if self.packet.len()==end+1 {
+			0x81
+		}
+		else {
+			self.process_packet();
+			self.packet.clear();
+			let status=self.status;
+			//report printing once, the "print" is instant
+			self.status&=!STATUS_PRINTING;
+			status
+		}
+	}
+}
+
+//run length encoding: a byte with bit 7 set repeats the next byte
+//(n&0x7F)+2 times, otherwise the next n+1 bytes are copied as they are.
+fn decompress(data: &[u8],out: &mut Vec<u8>){
+	let mut i=0;
+	while i<data.len(){
+		let n=data[i] as usize;
+		i+=1;
+		if n&0x80!=0 {
+			if let Some(&b)=data.get(i) {
+				out.extend(std::iter::repeat_n(b,(n&0x7F)+2));
+			}
+			i+=1;
+		}
+		else {
+			let end=(i+n+1).min(data.len());
+			out.extend_from_slice(&data[i..end]);
+			i=end;
+		}
+	}
+}
+
+//turns tile data into 8 bit gray pixels, 160 wide.
+fn render(image: &[u8],palette: u8)->Vec<u8>{
+	const SHADES: [u8;4] = [0xFF,0xAA,0x55,0x00];
+	let tiles=image.len()/16;
+	let height=tiles/TILES_PER_ROW*8;
+	let mut pixels=vec![0xFF;WIDTH*height];
+	for (y,row) in pixels.chunks_mut(WIDTH).enumerate(){
+		for (x,pixel) in row.iter_mut().enumerate(){
+			let tile=TileData::from_vram(image,(y/8)*TILES_PER_ROW+x/8);
+			let color=tile.pixel(x%8,y%8);
+			*pixel=SHADES[((palette>>(color*2))&3) as usize];
+		}
+	}
+	pixels
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{Printer,decompress,render};
+	use gb::serial::SerialDevice;
+	use std::env;
+
+	fn send(printer: &mut Printer,command: u8,data: &[u8])->u8{
+		let mut packet=vec![0x88,0x33,command,0,data.len() as u8,(data.len()>>8) as u8];
+		packet.extend_from_slice(data);
+		let checksum=packet[2..].iter().fold(0u16,|s,&b| s.wrapping_add(b as u16));
+		packet.extend_from_slice(&[checksum as u8,(checksum>>8) as u8,0,0]);
+		let replies: Vec<u8>=packet.iter().map(|&b| printer.exchange(b)).collect();
+		assert_eq!(replies[replies.len()-2],0x81);
+		replies[replies.len()-1]
+	}
+
+	#[test]
+	fn rle(){
+		let mut out=Vec::new();
+		decompress(&[0x81,0xAA,0x01,0x01,0x02],&mut out);
+		assert_eq!(out,vec![0xAA,0xAA,0xAA,0x01,0x02]);
+	}
+
+	#[test]
+	fn render_band(){
+		let pixels=render(&[0xFF;0x280],0xE4);
+		assert_eq!(pixels.len(),160*16);
+		assert!(pixels.iter().all(|&p| p==0x00));
+	}
+
+	#[test]
+	fn print_session(){
+		let mut printer=Printer::new(env::temp_dir());
+		assert_eq!(send(&mut printer,0x01,&[]),0x00);
+		assert_eq!(send(&mut printer,0x04,&[0;0x280]),0x08);
+		assert_eq!(printer.image.len(),0x280);
+		assert_eq!(send(&mut printer,0x02,&[1,0x13,0xE4,0x40]),0x02);
+		assert_eq!(send(&mut printer,0x0F,&[]),0x00);
+		assert!(printer.image.is_empty());
+	}
+}
@@ -11,7 +11,7 @@ impl Register{
 
 	//new register initialized with num.
 	pub fn new(num: u16) -> Self{
-		Register{hi:num as u8,lo: (num>>8) as u8}
+		Register{hi: (num>>8) as u8,lo: num as u8}
 	}
 
 	//set a value to a pair of registers.
@@ -62,6 +62,13 @@ mod tests{
 		assert_eq!(reg.get(),0x00FFu16);
 	}
 
+	#[test]
+	fn new_puts_the_high_byte_in_hi(){
+		let reg=Register::new(0x01B0);
+		assert_eq!((reg.hi,reg.lo),(0x01,0xB0));
+		assert_eq!(reg.get(),0x01B0);
+	}
+
 	#[test]
 	fn set_hi(){
 		let mut reg=Register::new(0);
@@ -0,0 +1,107 @@
+//Breakpoints on a register or flag changing value, optionally only when
+//it changes to a given value.
+use gb::cpu::CpuState;
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct RegisterWatch{
+	name: String,
+	//only trigger when the new value is this one.
+	filter: Option<u16>,
+}
+
+impl RegisterWatch{
+	//parses "HL", "A==FF" or "ZF==1". Registers are A F B C D E H L, the
+	//pairs AF BC DE HL, SP and PC; flags are ZF NF HF CF. Values are hex.
+	pub fn parse(text: &str)->Option<RegisterWatch>{
+		let mut parts=text.splitn(2,"==");
+		let name=parts.next()?.trim().to_uppercase();
+		let filter=match parts.next(){
+			Some(value) => {
+				let value=value.trim();
+				let value=value.strip_prefix("0x").unwrap_or(value);
+				Some(u16::from_str_radix(value,16).ok()?)
+			},
+			None        => None,
+		};
+		let watch=RegisterWatch{name,filter};
+		watch.value(&CpuState{a:0,f:0,b:0,c:0,d:0,e:0,h:0,l:0,sp:0,pc:0})?;
+		Some(watch)
+	}
+
+	pub fn name(&self)->&str{
+		&self.name
+	}
+
+	pub fn value(&self,state: &CpuState)->Option<u16>{
+		let pair=|hi: u8,lo: u8| (hi as u16)<<8 | lo as u16;
+		let flag=|bit: u8| (state.f>>bit&1) as u16;
+		Some(match &self.name[..]{
+			"A"  => state.a as u16,
+			"F"  => state.f as u16,
+			"B"  => state.b as u16,
+			"C"  => state.c as u16,
+			"D"  => state.d as u16,
+			"E"  => state.e as u16,
+			"H"  => state.h as u16,
+			"L"  => state.l as u16,
+			"AF" => pair(state.a,state.f),
+			"BC" => pair(state.b,state.c),
+			"DE" => pair(state.d,state.e),
+			"HL" => pair(state.h,state.l),
+			"SP" => state.sp,
+			"PC" => state.pc,
+			"ZF" => flag(7),
+			"NF" => flag(6),
+			"HF" => flag(5),
+			"CF" => flag(4),
+			_    => return None,
+		})
+	}
+
+	//the old and new values when the instruction between `before` and
+	//`after` tripped the watch.
+	pub fn check(&self,before: &CpuState,after: &CpuState)->Option<(u16,u16)>{
+		let (old,new)=(self.value(before)?,self.value(after)?);
+		if old==new || self.filter.is_some_and(|f| f!=new) {
+			return None;
+		}
+		Some((old,new))
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::RegisterWatch;
+	use gb::cpu::CpuState;
+
+	fn state()->CpuState{
+		CpuState{a:0,f:0,b:0,c:0,d:0,e:0,h:0x12,l:0x34,sp:0xFFFE,pc:0x150}
+	}
+
+	#[test]
+	fn parse(){
+		assert!(RegisterWatch::parse("hl").is_some());
+		assert!(RegisterWatch::parse("A == 0xFF").is_some());
+		assert!(RegisterWatch::parse("IX").is_none());
+		assert!(RegisterWatch::parse("A==zz").is_none());
+	}
+
+	#[test]
+	fn triggers_on_change_and_filter(){
+		let before=state();
+		let mut after=state();
+		let hl=RegisterWatch::parse("HL").unwrap();
+		assert_eq!(hl.check(&before,&after),None);
+		after.l=0x35;
+		assert_eq!(hl.check(&before,&after),Some((0x1234,0x1235)));
+		let a=RegisterWatch::parse("A==FF").unwrap();
+		after.a=0x01;
+		assert_eq!(a.check(&before,&after),None);
+		after.a=0xFF;
+		assert_eq!(a.check(&before,&after),Some((0x00,0xFF)));
+		let z=RegisterWatch::parse("ZF").unwrap();
+		after.f=0x80;
+		assert_eq!(z.check(&before,&after),Some((0,1)));
+	}
+}
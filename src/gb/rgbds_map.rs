@@ -0,0 +1,99 @@
+//Section layout from an RGBDS .map file (rgblink -m), to say which section
+//an address belongs to. Only rom sections are kept.
+//
+//  ROM0 bank #0:
+//  	SECTION: $0000-$0007 ($0008 bytes) ["RST0"]
+//  ROMX bank #1:
+//  	SECTION: $4000-$40ff ($0100 bytes) ["Level data"]
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Section{
+	pub name: String,
+	pub bank: usize,
+	pub start: u16,
+	pub end: u16,
+}
+
+#[derive(Debug,Default)]
+pub struct RgbdsMap{
+	sections: Vec<Section>,
+}
+
+impl RgbdsMap{
+	pub fn parse(text: &str)->RgbdsMap{
+		let mut sections=Vec::new();
+		let mut bank=None;
+		for line in text.lines(){
+			let line=line.trim();
+			if line.starts_with("ROM0 bank") || line.starts_with("ROMX bank") {
+				bank=line.split('#').nth(1)
+					.and_then(|b| b.trim_end_matches(':').trim().parse().ok());
+			}
+			else if line.contains(" bank #") {
+				//WRAM, SRAM, HRAM...
+				bank=None;
+			}
+			else if let (Some(bank),Some(section))=(bank,line.strip_prefix("SECTION:")) {
+				if let Some(section)=parse_section(section,bank) {
+					sections.push(section);
+				}
+			}
+		}
+		RgbdsMap{sections}
+	}
+
+	pub fn sections(&self)->&[Section]{
+		&self.sections
+	}
+
+	//section at `address` with `bank` mapped at 0x4000-0x7FFF.
+	pub fn section_at(&self,address: u16,bank: usize)->Option<&Section>{
+		let bank=match address{
+			0x0000..=0x3FFF => 0,
+			0x4000..=0x7FFF => bank,
+			_               => return None,
+		};
+		self.sections.iter().find(|s| s.bank==bank && s.start<=address && address<=s.end)
+	}
+}
+
+//`$4000-$40ff ($0100 bytes) ["Level data"]`
+fn parse_section(text: &str,bank: usize)->Option<Section>{
+	let text=text.trim();
+	let range=text.split_whitespace().next()?;
+	let mut bounds=range.split('-');
+	let start=u16::from_str_radix(bounds.next()?.trim_start_matches('$'),16).ok()?;
+	let end=u16::from_str_radix(bounds.next()?.trim_start_matches('$'),16).ok()?;
+	let name=text.split('"').nth(1)?.to_string();
+	Some(Section{name,bank,start,end})
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::RgbdsMap;
+
+	const MAP: &str = "ROM0 bank #0:
+	SECTION: $0000-$0007 ($0008 bytes) [\"RST0\"]
+	         $0000 = Reset
+	SECTION: $0150-$01ff ($00b0 bytes) [\"Main\"]
+	EMPTY: $0200-$3fff ($3e00 bytes)
+
+ROMX bank #2:
+	SECTION: $4000-$40ff ($0100 bytes) [\"Level data\"]
+
+WRAM0 bank #0:
+	SECTION: $c000-$c0ff ($0100 bytes) [\"Variables\"]
+";
+
+	#[test]
+	fn sections_by_bank(){
+		let map=RgbdsMap::parse(MAP);
+		assert_eq!(map.sections().len(),3);
+		assert_eq!(map.section_at(0x0160,1).map(|s| &s.name[..]),Some("Main"));
+		assert_eq!(map.section_at(0x4010,2).map(|s| &s.name[..]),Some("Level data"));
+		assert!(map.section_at(0x4010,1).is_none());
+		assert!(map.section_at(0x0300,1).is_none());
+		assert!(map.section_at(0xC000,1).is_none());
+	}
+}
@@ -0,0 +1,162 @@
+//MBC3 real time clock. It counts emulated time (4194304 cycles a second)
+//so runs stay deterministic.
+
+const CYCLES_PER_SECOND: u32 = 4194304;
+
+//register numbers as selected through 0x4000-0x5FFF.
+pub const SECONDS: u8 = 0x08;
+pub const MINUTES: u8 = 0x09;
+pub const HOURS: u8 = 0x0A;
+pub const DAYS_LOW: u8 = 0x0B;
+pub const DAYS_HIGH: u8 = 0x0C;
+
+#[derive(Debug,Clone,Default)]
+pub struct Rtc{
+	seconds: u8,
+	minutes: u8,
+	hours: u8,
+	//9 bits
+	days: u16,
+	halted: bool,
+	//set when days overflows, until the game clears it.
+	day_carry: bool,
+	//cycles into the current second.
+	cycles: u32,
+	//what the game reads, copied by a 0 then 1 write to 0x6000-0x7FFF.
+	latched: [u8;5],
+	latch_armed: bool,
+}
+
+impl Rtc{
+	pub fn new()->Self{
+		Rtc::default()
+	}
+
+	pub fn seconds(&self)->u8{
+		self.seconds
+	}
+
+	pub fn minutes(&self)->u8{
+		self.minutes
+	}
+
+	pub fn hours(&self)->u8{
+		self.hours
+	}
+
+	pub fn days(&self)->u16{
+		self.days
+	}
+
+	pub fn halted(&self)->bool{
+		self.halted
+	}
+
+	pub fn step(&mut self,cycles: u32){
+		if self.halted {
+			return;
+		}
+		self.cycles+=cycles;
+		while self.cycles>=CYCLES_PER_SECOND{
+			self.cycles-=CYCLES_PER_SECOND;
+			self.tick();
+		}
+	}
+
+	//the counters wrap on their bit width, not only on 60/24, which some
+	//games rely on after writing out of range values.
+	fn tick(&mut self){
+		self.seconds=(self.seconds+1)&0x3F;
+		if self.seconds!=60 {
+			return;
+		}
+		self.seconds=0;
+		self.minutes=(self.minutes+1)&0x3F;
+		if self.minutes!=60 {
+			return;
+		}
+		self.minutes=0;
+		self.hours=(self.hours+1)&0x1F;
+		if self.hours!=24 {
+			return;
+		}
+		self.hours=0;
+		self.days+=1;
+		if self.days>0x1FF {
+			self.days=0;
+			self.day_carry=true;
+		}
+	}
+
+	pub fn write_latch(&mut self,data: u8){
+		if self.latch_armed && data==1 {
+			self.latched=[self.seconds,self.minutes,self.hours,self.days as u8,self.day_high()];
+		}
+		self.latch_armed=data==0;
+	}
+
+	fn day_high(&self)->u8{
+		(self.days>>8) as u8 | (self.halted as u8)<<6 | (self.day_carry as u8)<<7
+	}
+
+	pub fn read(&self,register: u8)->u8{
+		self.latched[(register-SECONDS) as usize]
+	}
+
+	pub fn write(&mut self,register: u8,data: u8){
+		match register{
+			SECONDS   => {
+				self.seconds=data&0x3F;
+				self.cycles=0;
+			},
+			MINUTES   => self.minutes=data&0x3F,
+			HOURS     => self.hours=data&0x1F,
+			DAYS_LOW  => self.days=(self.days&0x100) | data as u16,
+			_         => {
+				self.days=(self.days&0xFF) | ((data&1) as u16)<<8;
+				self.halted=data&0x40!=0;
+				self.day_carry=data&0x80!=0;
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{Rtc,CYCLES_PER_SECOND,SECONDS,MINUTES,HOURS,DAYS_HIGH};
+
+	#[test]
+	fn rollover_and_latch(){
+		let mut rtc=Rtc::new();
+		rtc.write(SECONDS,59);
+		rtc.write(MINUTES,59);
+		rtc.write(HOURS,23);
+		rtc.step(CYCLES_PER_SECOND);
+		assert_eq!((rtc.days(),rtc.hours(),rtc.minutes(),rtc.seconds()),(1,0,0,0));
+		//nothing visible until latched
+		assert_eq!(rtc.read(HOURS),0);
+		rtc.write(HOURS,5);
+		rtc.write_latch(0);
+		rtc.write_latch(1);
+		assert_eq!(rtc.read(HOURS),5);
+	}
+
+	#[test]
+	fn halt_and_day_carry(){
+		let mut rtc=Rtc::new();
+		rtc.write(DAYS_HIGH,0x41);
+		rtc.step(CYCLES_PER_SECOND*2);
+		assert_eq!(rtc.seconds(),0);
+		rtc.write(DAYS_HIGH,0x01);
+		rtc.write(0x0B,0xFF);
+		rtc.write(HOURS,23);
+		rtc.write(MINUTES,59);
+		rtc.write(SECONDS,59);
+		rtc.step(CYCLES_PER_SECOND);
+		rtc.write_latch(0);
+		rtc.write_latch(1);
+		assert_eq!(rtc.days(),0);
+		assert_eq!(rtc.read(DAYS_HIGH),0x80);
+	}
+}
@@ -0,0 +1,78 @@
+//MBC5 rumble motor. Games pulse the motor on and off quickly to get
+//weaker rumble, so the state is averaged over a frame into an intensity
+//instead of forwarding every toggle.
+
+//intensity steps, small duty cycle jitter between frames is dropped.
+const LEVELS: f32 = 8.0;
+
+#[derive(Debug,Default)]
+pub struct Rumble{
+	on: bool,
+	//cycles with the motor on, and in total, since the last frame.
+	on_cycles: u32,
+	cycles: u32,
+	//last intensity reported.
+	reported: f32,
+}
+
+impl Rumble{
+	pub fn new()->Self{
+		Rumble::default()
+	}
+
+	pub fn set_motor(&mut self,on: bool){
+		self.on=on;
+	}
+
+	pub fn step(&mut self,cycles: u32){
+		self.cycles+=cycles;
+		if self.on {
+			self.on_cycles+=cycles;
+		}
+	}
+
+	//intensity (0 to 1) over the frame that just ended, only when it
+	//changed since the last report.
+	pub fn end_frame(&mut self)->Option<f32>{
+		if self.cycles==0 {
+			return None;
+		}
+		let duty=self.on_cycles as f32/self.cycles as f32;
+		let intensity=(duty*LEVELS).round()/LEVELS;
+		self.on_cycles=0;
+		self.cycles=0;
+		if intensity==self.reported {
+			return None;
+		}
+		self.reported=intensity;
+		Some(intensity)
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::Rumble;
+
+	#[test]
+	fn toggles_become_intensity(){
+		let mut rumble=Rumble::new();
+		rumble.step(1000);
+		assert_eq!(rumble.end_frame(),None);
+		for _ in 0..10 {
+			rumble.set_motor(true);
+			rumble.step(100);
+			rumble.set_motor(false);
+			rumble.step(100);
+		}
+		assert_eq!(rumble.end_frame(),Some(0.5));
+		//same duty cycle, nothing new to report
+		rumble.set_motor(true);
+		rumble.step(51);
+		rumble.set_motor(false);
+		rumble.step(49);
+		assert_eq!(rumble.end_frame(),None);
+		rumble.step(100);
+		assert_eq!(rumble.end_frame(),Some(0.0));
+	}
+}
@@ -0,0 +1,79 @@
+//Battery backed save ram files. Saves are stored as raw ram dumps, the
+//same .sav layout most emulators and flashcarts use.
+use gb::cartridge::{self,Cartridge};
+
+use std::fs;
+use std::io;
+use std::path::{Path,PathBuf};
+
+//where the save for a rom lives: next to it, named after the header title
+//and the rom's checksum, so hacks and revisions sharing a title (or a
+//rom renamed on disk) don't share a save.
+pub fn save_path(rom_path: &Path,cart: &Cartridge)->PathBuf{
+	let name=format!("{}-{:04X}.sav",sanitize_title(&cart.title()),
+		cartridge::compute_global_checksum(cart.rom()));
+	rom_path.with_file_name(name)
+}
+
+//where saves used to go, the rom path with a .sav extension.
+pub fn legacy_save_path(rom_path: &Path)->PathBuf{
+	rom_path.with_extension("sav")
+}
+
+//moves a save from where older versions kept it, unless there's one
+//at the new place already. Returns the old path when it moved.
+pub fn migrate(rom_path: &Path,cart: &Cartridge)->io::Result<Option<PathBuf>>{
+	let legacy=legacy_save_path(rom_path);
+	let path=save_path(rom_path,cart);
+	if path.exists() || !legacy.is_file() {
+		return Ok(None);
+	}
+	fs::rename(&legacy,&path)?;
+	Ok(Some(legacy))
+}
+
+//keeps letters, digits, - and _ so the title is a valid file name
+//everywhere. Untitled roms (homebrew) get a placeholder.
+pub fn sanitize_title(title: &str)->String{
+	let name: String=title.trim().chars()
+		.map(|c| if c.is_ascii_alphanumeric() || c=='-' || c=='_' {c} else {'_'})
+		.collect();
+	if name.is_empty() {String::from("untitled")} else {name}
+}
+
+//fits a save from elsewhere to `size` bytes of cartridge ram. Shorter
+//files are padded with 0xFF (erased sram), longer ones usually carry an
+//RTC footer or padding that is dropped.
+pub fn fit(data: &[u8],size: usize)->Vec<u8>{
+	let mut ram=data[..data.len().min(size)].to_vec();
+	ram.resize(size,0xFF);
+	ram
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{fit,sanitize_title,save_path};
+	use gb::cartridge::Cartridge;
+	use gb::testrom;
+	use std::path::Path;
+
+	#[test]
+	fn fit_pads_and_truncates(){
+		assert_eq!(fit(&[1,2],4),vec![1,2,0xFF,0xFF]);
+		assert_eq!(fit(&[1,2,3,4,5],4),vec![1,2,3,4]);
+	}
+
+	#[test]
+	fn save_names_tell_roms_apart(){
+		assert_eq!(sanitize_title("POKEMON RED"),"POKEMON_RED");
+		assert_eq!(sanitize_title("A/B:C"),"A_B_C");
+		assert_eq!(sanitize_title(""),"untitled");
+		let rom=testrom::tiny_rom();
+		let mut hack=rom.clone();
+		hack[0x200]=0x12;
+		let path=|rom: Vec<u8>| save_path(Path::new("roms/game.gb"),&Cartridge::new(rom));
+		assert_ne!(path(rom.clone()),path(hack));
+		assert!(path(rom).starts_with("roms"));
+	}
+}
@@ -0,0 +1,111 @@
+//Hardware events due at a later cycle (a serial transfer finishing, a
+//timer reload...), kept in a heap so the next one is always at hand
+//instead of every peripheral counting down its own cycles.
+use std::collections::BinaryHeap;
+use std::cmp::{Ordering,Reverse};
+
+pub struct Scheduler<E>{
+	//cycles advanced so far.
+	now: u64,
+	queue: BinaryHeap<Reverse<Entry<E>>>,
+	//events scheduled so far, so ones due on the same cycle come out in
+	//the order they went in.
+	scheduled: u64,
+}
+
+struct Entry<E>{
+	cycle: u64,
+	order: u64,
+	event: E,
+}
+
+impl<E> PartialEq for Entry<E>{
+	fn eq(&self,other: &Self)->bool{
+		(self.cycle,self.order)==(other.cycle,other.order)
+	}
+}
+
+impl<E> Eq for Entry<E>{}
+
+impl<E> PartialOrd for Entry<E>{
+	fn partial_cmp(&self,other: &Self)->Option<Ordering>{
+		Some(self.cmp(other))
+	}
+}
+
+impl<E> Ord for Entry<E>{
+	fn cmp(&self,other: &Self)->Ordering{
+		(self.cycle,self.order).cmp(&(other.cycle,other.order))
+	}
+}
+
+impl<E> Default for Scheduler<E>{
+	fn default()->Self{
+		Scheduler::new()
+	}
+}
+
+impl<E> Scheduler<E>{
+	pub fn new()->Self{
+		Scheduler{now: 0,queue: BinaryHeap::new(),scheduled: 0}
+	}
+
+	pub fn now(&self)->u64{
+		self.now
+	}
+
+	pub fn schedule_in(&mut self,cycles: u64,event: E){
+		let entry=Entry{cycle: self.now+cycles,order: self.scheduled,event};
+		self.scheduled+=1;
+		self.queue.push(Reverse(entry));
+	}
+
+	//drops every pending event `cancel` returns true for.
+	pub fn cancel(&mut self,cancel: impl Fn(&E)->bool){
+		self.queue.retain(|entry| !cancel(&entry.0.event));
+	}
+
+	pub fn advance(&mut self,cycles: u64){
+		self.now+=cycles;
+	}
+
+	//the next event that is due, earliest first.
+	pub fn pop_due(&mut self)->Option<E>{
+		match self.queue.peek(){
+			Some(entry) if entry.0.cycle<=self.now => self.queue.pop().map(|entry| entry.0.event),
+			_                                      => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::Scheduler;
+
+	#[test]
+	fn events_come_out_in_cycle_order(){
+		let mut scheduler=Scheduler::new();
+		scheduler.schedule_in(10,"b");
+		scheduler.schedule_in(5,"a");
+		scheduler.schedule_in(10,"c");
+		scheduler.advance(4);
+		assert_eq!(scheduler.pop_due(),None);
+		scheduler.advance(1);
+		assert_eq!(scheduler.pop_due(),Some("a"));
+		scheduler.advance(7);
+		let due: Vec<_>=::std::iter::from_fn(|| scheduler.pop_due()).collect();
+		assert_eq!(due,vec!["b","c"]);
+	}
+
+	#[test]
+	fn cancel(){
+		let mut scheduler=Scheduler::new();
+		scheduler.schedule_in(1,1);
+		scheduler.schedule_in(2,2);
+		scheduler.cancel(|&event| event==1);
+		scheduler.advance(2);
+		assert_eq!(scheduler.pop_due(),Some(2));
+		assert_eq!(scheduler.pop_due(),None);
+	}
+}
@@ -0,0 +1,120 @@
+//Quick synthetic checks of the core, run by `selftest` so users can tell
+//whether their build works before blaming a rom.
+use gb::cartridge::Cartridge;
+use gb::gameboy::GameBoy;
+use gb::interconnect::RamInit;
+use gb::opcode::Opcode;
+use gb::register::Register;
+use gb::testrom::{self,TINY_PROGRAM};
+use gb::cartridge::RAW_LOAD_ADDRESS;
+
+pub enum Status{
+	Pass(String),
+	Fail(String),
+	//the hardware being checked isn't emulated yet.
+	Skip(String),
+}
+
+pub struct Check{
+	pub name: &'static str,
+	pub status: Status,
+}
+
+pub fn run()->Vec<Check>{
+	vec![
+		Check{name: "opcode table",status: opcode_table()},
+		Check{name: "register pairs",status: register_pairs()},
+		Check{name: "mbc banking",status: mbc_banking()},
+		Check{name: "timer frequencies",status: Status::Skip(String::from("no timer yet"))},
+		Check{name: "test rom",status: test_rom()},
+	]
+}
+
+fn opcode_table()->Status{
+	let mut implemented=0;
+	for byte in 0..=255u8{
+		if let Some(op)=Opcode::decode(byte) {
+			if op as u8!=byte {
+				return Status::Fail(format!("{:?} decodes from {:#04X} but is {:#04X}",op,byte,op as u8));
+			}
+			if op.cycles().0==0 {
+				return Status::Fail(format!("{:?} has no cycle count",op));
+			}
+			implemented+=1;
+		}
+	}
+	Status::Pass(format!("{} of 256 opcodes decoded",implemented))
+}
+
+fn register_pairs()->Status{
+	for &value in &[0x0000,0x00FF,0xFF00,0x1234,0xFFFF]{
+		let mut reg=Register::new(0);
+		reg.set(value);
+		if reg.get()!=value {
+			return Status::Fail(format!("set/get {:#06X} gave {:#06X}",value,reg.get()));
+		}
+		let mut reg=Register::new(0);
+		reg.set_hi((value>>8) as u8);
+		reg.set_lo(value as u8);
+		if reg.get()!=value {
+			return Status::Fail(format!("set_hi/set_lo {:#06X} gave {:#06X}",value,reg.get()));
+		}
+		let reg=Register::new(value);
+		if reg.get()!=value {
+			return Status::Fail(format!("new {:#06X} gave {:#06X}",value,reg.get()));
+		}
+	}
+	Status::Pass(String::from("set/get, hi/lo and new agree"))
+}
+
+//every bank of an 8 bank rom maps at 0x4000, bank 0 selecting bank 1 where
+//the controller does that.
+fn mbc_banking()->Status{
+	for &(cart_type,zero_is_one) in &[(0x01,true),(0x11,true),(0x19,false)]{
+		let mut rom=vec![0;0x4000*8];
+		rom[0x147]=cart_type;
+		rom[0x148]=0x02;
+		for bank in 0..8 {
+			rom[bank*0x4000+0x10]=bank as u8;
+		}
+		let mut cart=Cartridge::new(rom);
+		for bank in 0..8u8 {
+			cart.write(0x2000,bank);
+			let expected=if bank==0 && zero_is_one {1} else {bank};
+			let read=cart.read(0x4010);
+			if read!=expected {
+				return Status::Fail(format!("cart type {:#04X}: bank {} read bank {}",cart_type,bank,read));
+			}
+		}
+	}
+	Status::Pass(String::from("MBC1, MBC3 and MBC5"))
+}
+
+//the tiny rom has to loop in place for a whole frame.
+fn test_rom()->Status{
+	let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+	gb.set_verbose(false);
+	gb.step_frame();
+	let pc=gb.cpu().state().pc;
+	let end=RAW_LOAD_ADDRESS+TINY_PROGRAM.len() as u16;
+	if pc<RAW_LOAD_ADDRESS || pc>=end {
+		return Status::Fail(format!("pc escaped to {:#06X}",pc));
+	}
+	Status::Pass(format!("ran a frame, pc at {:#06X}",pc))
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{run,Status};
+
+	//selftest is what users run first, it must never ship red.
+	#[test]
+	fn no_check_fails(){
+		for check in run(){
+			if let Status::Fail(ref reason)=check.status {
+				panic!("{} failed: {}",check.name,reason);
+			}
+		}
+	}
+}
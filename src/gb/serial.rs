@@ -0,0 +1,158 @@
+//Link port, SB (0xFF01) and SC (0xFF02). Only the internal clock is
+//supported: a transfer shifts 8 bits out at 8192Hz and swaps SB with
+//whatever is plugged in at the other end. Interconnect schedules the end
+//of a transfer TRANSFER_CYCLES after it starts.
+
+use gb::peripheral::Peripheral;
+use gb::interrupt;
+
+//cycles to shift 8 bits at 8192Hz.
+pub const TRANSFER_CYCLES: u64 = 8*512;
+
+//anything that can sit at the other end of the link cable.
+pub trait SerialDevice{
+	//receives the byte we shifted out and returns the one shifted in.
+	fn exchange(&mut self,byte: u8)->u8;
+
+	//called every step while we wait for the other end to clock a
+	//transfer. `sb` is what we'd shift out, returns the byte shifted in
+	//once the transfer happened.
+	fn external_clock(&mut self,_sb: u8)->Option<u8>{
+		None
+	}
+}
+
+//nothing plugged in, the line is pulled high.
+pub struct Disconnected;
+
+impl SerialDevice for Disconnected{
+	fn exchange(&mut self,_byte: u8)->u8{
+		0xFF
+	}
+}
+
+pub struct Serial{
+	sb: u8,
+	sc: u8,
+	device: Box<dyn SerialDevice>,
+	//a transfer finished since the interrupt was last taken.
+	interrupt: bool,
+	//bytes shifted out since they were last taken.
+	sent: Vec<u8>,
+}
+
+impl Default for Serial{
+	fn default()->Self{
+		Serial::new()
+	}
+}
+
+impl Serial{
+	pub fn new()->Self{
+		Serial{sb: 0,sc: 0,device: Box::new(Disconnected),interrupt: false,
+			sent: Vec::new()}
+	}
+
+	pub fn connect(&mut self,device: Box<dyn SerialDevice>){
+		self.device=device;
+	}
+
+	//clears the registers and any transfer in flight, the device stays
+	//plugged in.
+	pub fn reset(&mut self){
+		let device=::std::mem::replace(&mut self.device,Box::new(Disconnected));
+		*self=Serial::new();
+		self.device=device;
+	}
+
+	//bytes shifted out since the last call, oldest first.
+	pub fn take_sent(&mut self)->Vec<u8>{
+		::std::mem::take(&mut self.sent)
+	}
+
+	//SC asks for a transfer on the internal clock.
+	pub fn internal_transfer(&self)->bool{
+		self.sc&0x81==0x81
+	}
+
+	//the bits of an internal clock transfer are all shifted.
+	pub fn complete_transfer(&mut self){
+		if self.internal_transfer() {
+			let byte=self.device.exchange(self.sb);
+			self.finish_transfer(byte);
+		}
+	}
+
+	fn finish_transfer(&mut self,byte: u8){
+		self.sent.push(self.sb);
+		self.sb=byte;
+		self.sc&=0x7F;
+		self.interrupt=true;
+	}
+}
+
+impl Peripheral for Serial{
+	fn handles(&self,address: u16)->bool{
+		address==0xFF01 || address==0xFF02
+	}
+
+	fn read(&self,address: u16)->u8{
+		match address{
+			0xFF01 => self.sb,
+			//unused bits read as 1
+			_      => self.sc|0x7E,
+		}
+	}
+
+	fn write(&mut self,address: u16,data: u8){
+		match address{
+			0xFF01 => self.sb=data,
+			_      => self.sc=data,
+		}
+	}
+
+	fn step(&mut self,_cycles: u32){
+		if self.sc&0x81==0x80 {
+			if let Some(byte)=self.device.external_clock(self.sb) {
+				self.finish_transfer(byte);
+			}
+		}
+	}
+
+	fn pending_interrupt(&mut self)->u8{
+		if ::std::mem::replace(&mut self.interrupt,false) {interrupt::SERIAL} else {0}
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{Serial,SerialDevice};
+	use gb::peripheral::Peripheral;
+	use gb::interrupt;
+
+	struct Echo;
+
+	impl SerialDevice for Echo{
+		fn exchange(&mut self,byte: u8)->u8{
+			!byte
+		}
+	}
+
+	#[test]
+	fn internal_clock_transfer(){
+		let mut serial=Serial::new();
+		serial.connect(Box::new(Echo));
+		serial.write(0xFF01,0x0F);
+		serial.write(0xFF02,0x81);
+		assert!(serial.internal_transfer());
+		assert_eq!(serial.read(0xFF02),0xFF);
+		assert_eq!(serial.pending_interrupt(),0);
+		serial.complete_transfer();
+		assert_eq!(serial.read(0xFF01),0xF0);
+		assert_eq!(serial.read(0xFF02),0x7F);
+		assert_eq!(serial.pending_interrupt(),interrupt::SERIAL);
+		assert_eq!(serial.pending_interrupt(),0);
+		assert_eq!(serial.take_sent(),vec![0x0F]);
+	}
+}
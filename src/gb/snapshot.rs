@@ -0,0 +1,44 @@
+//Immutable copies of the machine state for UIs reading from another
+//thread. The core publishes one every frame, so readers never see a half
+//updated machine.
+use gb::cpu::CpuState;
+use std::ops::RangeInclusive;
+use std::sync::{Arc,Mutex};
+
+#[derive(Debug,Clone)]
+pub struct Snapshot{
+	pub cycles: u64,
+	pub frame: u64,
+	pub cpu: CpuState,
+	//0xFF00-0xFF7F and IE, unimplemented registers read 0xFF.
+	pub io: Vec<u8>,
+	pub ie: u8,
+	//the requested memory ranges with their contents.
+	pub memory: Vec<(RangeInclusive<u16>,Vec<u8>)>,
+}
+
+impl Snapshot{
+	//byte at `address` if it falls in one of the captured ranges.
+	pub fn read(&self,address: u16)->Option<u8>{
+		self.memory.iter()
+			.find(|&(range,_)| range.contains(&address))
+			.map(|(range,bytes)| bytes[(address-range.start()) as usize])
+	}
+}
+
+//handle for readers, cheap to clone and send to other threads.
+#[derive(Clone,Default)]
+pub struct SnapshotReader{
+	latest: Arc<Mutex<Option<Arc<Snapshot>>>>,
+}
+
+impl SnapshotReader{
+	//last published snapshot, if a frame has completed yet.
+	pub fn latest(&self)->Option<Arc<Snapshot>>{
+		self.latest.lock().unwrap().clone()
+	}
+
+	pub fn publish(&self,snapshot: Snapshot){
+		*self.latest.lock().unwrap()=Some(Arc::new(snapshot));
+	}
+}
@@ -0,0 +1,115 @@
+//Sprite debugging views: the screen with only sprites on it, each tinted
+//by its OAM index, and which sprites the 10 a line limit drops. Built from
+//OAM and VRAM alone, so it works without the PPU.
+use gb::video::{self,TileData,SpriteEntry,SCREEN_WIDTH,SCREEN_HEIGHT};
+
+const BACKGROUND: [u8;3] = [255,255,255];
+
+//a distinct hue for each OAM index.
+pub fn tint(index: usize)->[u8;3]{
+	//steps of 7/40 of the hue circle, so neighbouring indexes differ a lot
+	let hue=(index*7%video::SPRITE_COUNT) as u32*6*255/video::SPRITE_COUNT as u32;
+	let (sector,offset)=(hue/255,(hue%255) as u8);
+	match sector{
+		0 => [255,offset,0],
+		1 => [255-offset,255,0],
+		2 => [0,255,offset],
+		3 => [0,255-offset,255],
+		4 => [offset,0,255],
+		_ => [255,0,255-offset],
+	}
+}
+
+//160x144 pixels, row by row. Color numbers 1-3 are drawn as lighter to
+//darker shades of the sprite's tint, 0 is transparent. Overlaps follow DMG
+//priority: lower x wins, then lower OAM index.
+pub fn composite(oam: &[u8],vram: &[u8],height: i16)->Vec<[u8;3]>{
+	let sprites=video::sprites(oam);
+	let mut pixels=vec![BACKGROUND;SCREEN_WIDTH*SCREEN_HEIGHT];
+	for line in 0..SCREEN_HEIGHT {
+		let (mut shown,_)=video::line_sprites(&sprites,line as i16,height);
+		shown.sort_by_key(|&i| (sprites[i].x,i));
+		for &index in shown.iter().rev(){
+			let sprite=&sprites[index];
+			let row=sprite_row(sprite,line as i16,height,vram);
+			for (x,&color) in row.iter().enumerate(){
+				let screen_x=sprite.screen_x()+x as i16;
+				if color==0 || screen_x<0 || screen_x>=SCREEN_WIDTH as i16 {
+					continue;
+				}
+				let shade=|channel: u8| (channel as u16*(4-color as u16)/3) as u8;
+				let tint=tint(index);
+				pixels[line*SCREEN_WIDTH+screen_x as usize]=[shade(tint[0]),shade(tint[1]),shade(tint[2])];
+			}
+		}
+	}
+	pixels
+}
+
+//color numbers of the sprite's 8 pixels on `line`, flips applied.
+fn sprite_row(sprite: &SpriteEntry,line: i16,height: i16,vram: &[u8])->[u8;8]{
+	let mut y=(line-sprite.screen_y()) as usize;
+	if sprite.flip_y() {
+		y=height as usize-1-y;
+	}
+	//8x16 sprites ignore bit 0 of the tile number
+	let tile=if height==16 {sprite.tile&0xFE} else {sprite.tile} as usize+y/8;
+	let tile=TileData::from_vram(vram,tile);
+	let mut row=[0;8];
+	for (x,color) in row.iter_mut().enumerate(){
+		let x=if sprite.flip_x() {7-x} else {x};
+		*color=tile.pixel(x,y%8);
+	}
+	row
+}
+
+//"lines 40-47: 10 11" for every run of lines dropping the same sprites.
+pub fn dropped_sprites(oam: &[u8],height: i16)->Vec<String>{
+	let sprites=video::sprites(oam);
+	let mut runs: Vec<(usize,usize,Vec<usize>)>=Vec::new();
+	for line in 0..SCREEN_HEIGHT {
+		let (_,dropped)=video::line_sprites(&sprites,line as i16,height);
+		match runs.last_mut(){
+			Some(run) if run.1+1==line && run.2==dropped => run.1=line,
+			_ if !dropped.is_empty()                     => runs.push((line,line,dropped)),
+			_                                            => {},
+		}
+	}
+	runs.iter().map(|&(first,last,ref dropped)| {
+		let lines=if first==last {format!("line {}",first)} else {format!("lines {}-{}",first,last)};
+		let indexes: Vec<String>=dropped.iter().map(|i| i.to_string()).collect();
+		format!("{}: {}",lines,indexes.join(" "))
+	}).collect()
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{composite,dropped_sprites,tint,BACKGROUND};
+
+	#[test]
+	fn tints_sprites_by_index(){
+		let mut vram=vec![0;0x2000];
+		//tile 1, first row all color 3
+		vram[16]=0xFF;
+		vram[17]=0xFF;
+		let mut oam=vec![0;160];
+		oam[4..8].copy_from_slice(&[16,8,1,0]);
+		let pixels=composite(&oam,&vram,8);
+		assert_eq!(pixels[0],[tint(1)[0]/3,tint(1)[1]/3,tint(1)[2]/3]);
+		assert_eq!(pixels[8],BACKGROUND);
+		assert_eq!(pixels[160],BACKGROUND);
+		assert_ne!(tint(0),tint(1));
+	}
+
+	#[test]
+	fn groups_dropped_lines(){
+		let mut oam=vec![0;160];
+		for i in 0..11 {
+			oam[i*4]=16;
+		}
+		assert_eq!(dropped_sprites(&oam,8),vec!["lines 0-7: 10"]);
+		oam[10*4]=16+7;
+		assert_eq!(dropped_sprites(&oam,8),vec!["line 7: 10"]);
+	}
+}
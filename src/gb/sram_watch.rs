@@ -0,0 +1,28 @@
+//Watching writes to cartridge ram, to find where a game saves.
+use std::fmt;
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum SramWatch{
+	//print every write and keep going.
+	Log,
+	//print the first write and stop there.
+	Break,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct SramWrite{
+	//address of the instruction doing the write.
+	pub pc: u16,
+	//ram bank mapped at the time.
+	pub bank: usize,
+	pub address: u16,
+	pub data: u8,
+}
+
+impl fmt::Display for SramWrite{
+	fn fmt(&self,f: &mut fmt::Formatter)->fmt::Result{
+		write!(f,"PC {:04X} wrote {:02X} to {:04X} (ram bank {}, offset {:04X})",
+			self.pc,self.data,self.address,self.bank,
+			self.bank*0x2000+(self.address-0xA000) as usize)
+	}
+}
@@ -0,0 +1,12 @@
+//Smallest rom the core can run, for examples and tests that need a
+//cartridge but don't care what it does.
+
+use gb::cartridge::{raw_image,RAW_LOAD_ADDRESS};
+
+//nop; jp RAW_LOAD_ADDRESS, forever.
+pub const TINY_PROGRAM: [u8;4] = [0x00,0xC3,RAW_LOAD_ADDRESS as u8,(RAW_LOAD_ADDRESS>>8) as u8];
+
+//TINY_PROGRAM in a 32KB MBC0 cartridge with a valid header.
+pub fn tiny_rom()->Vec<u8>{
+	raw_image(&TINY_PROGRAM).unwrap()
+}
@@ -0,0 +1,86 @@
+//Instrumentation cross-checking the cycles each executed instruction took
+//against the table in opcode.rs, so the table and the cpu can't silently
+//disagree.
+use gb::opcode::Opcode;
+use std::collections::BTreeMap;
+
+#[derive(Debug,Default)]
+struct OpcodeTiming{
+	//how many times each cycle count was seen.
+	histogram: BTreeMap<usize,u64>,
+	mismatches: u64,
+}
+
+#[derive(Debug,Default)]
+pub struct TimingCheck{
+	opcodes: BTreeMap<u8,OpcodeTiming>,
+}
+
+impl TimingCheck{
+	pub fn new()->Self{
+		TimingCheck::default()
+	}
+
+	//records an instruction, logging the first mismatch of every opcode.
+	//Returns whether the cycles matched the table.
+	pub fn record(&mut self,pc: u16,opcode: u8,cycles: usize)->bool{
+		let timing=self.opcodes.entry(opcode).or_default();
+		*timing.histogram.entry(cycles).or_insert(0)+=1;
+		let ok=match Opcode::decode(opcode){
+			Some(op) => {
+				let (base,taken)=op.cycles();
+				cycles==base || Some(cycles)==taken
+			},
+			None     => true,
+		};
+		if !ok {
+			if timing.mismatches==0 {
+				eprintln!("Timing mismatch: opcode {:#04X} at {:#06X} took {} cycles, expected {}",
+					opcode,pc,cycles,expected(opcode));
+			}
+			timing.mismatches+=1;
+		}
+		ok
+	}
+
+	pub fn mismatches(&self)->u64{
+		self.opcodes.values().map(|t| t.mismatches).sum()
+	}
+
+	//one line per executed opcode: expected cycles, then what was seen.
+	pub fn report(&self)->Vec<String>{
+		self.opcodes.iter().map(|(&opcode,timing)| {
+			let seen: Vec<String>=timing.histogram.iter()
+				.map(|(cycles,count)| format!("{}x{}",cycles,count))
+				.collect();
+			let flag=if timing.mismatches>0 {" MISMATCH"} else {""};
+			format!("{:#04X} expected {:<6} seen {}{}",opcode,expected(opcode),seen.join(" "),flag)
+		}).collect()
+	}
+}
+
+fn expected(opcode: u8)->String{
+	match Opcode::decode(opcode).map(|op| op.cycles()){
+		Some((base,Some(taken))) => format!("{}/{}",base,taken),
+		Some((base,None))        => base.to_string(),
+		None                     => String::from("?"),
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::TimingCheck;
+
+	#[test]
+	fn mismatches_are_counted(){
+		let mut check=TimingCheck::new();
+		assert!(check.record(0x100,0x00,4));
+		assert!(check.record(0x101,0x00,4));
+		assert!(!check.record(0x102,0x01,0));
+		assert_eq!(check.mismatches(),1);
+		let report=check.report();
+		assert_eq!(report[0],"0x00 expected 4      seen 4x2");
+		assert_eq!(report[1],"0x01 expected 12     seen 0x1 MISMATCH");
+	}
+}
@@ -0,0 +1,119 @@
+//Compares our execution against a reference log from another emulator.
+//Lines use the gameboy-doctor format, one per instruction, taken before
+//the instruction runs:
+//A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02
+use gb::cpu::CpuState;
+use std::fmt::{Display,Result,Formatter};
+use std::io::BufRead;
+
+pub enum Outcome{
+	Match,
+	//the reference ran out of lines.
+	Ended(usize),
+	Diverged{line: usize,expected: CpuState,actual: CpuState},
+}
+
+pub struct TraceComparer{
+	reference: Box<dyn BufRead>,
+	line: usize,
+}
+
+impl TraceComparer{
+	pub fn new(reference: Box<dyn BufRead>)->Self{
+		TraceComparer{reference,line: 0}
+	}
+
+	//checks the state before the next instruction against the reference.
+	pub fn check(&mut self,actual: CpuState)->Outcome{
+		let mut text=String::new();
+		loop{
+			text.clear();
+			let read=self.reference.read_line(&mut text)
+				.unwrap_or_else(|e| panic!("Cannot read reference trace: {}",e));
+			if read==0 {
+				return Outcome::Ended(self.line);
+			}
+			self.line+=1;
+			if !text.trim().is_empty() {
+				break;
+			}
+		}
+		let expected=parse_line(&text).unwrap_or_else(||
+			panic!("Malformed reference trace line {}: {}",self.line,text.trim())
+		);
+		if expected==actual {
+			Outcome::Match
+		}
+		else {
+			Outcome::Diverged{line: self.line,expected,actual}
+		}
+	}
+}
+
+fn parse_line(text: &str)->Option<CpuState>{
+	let mut state=CpuState{a:0,f:0,b:0,c:0,d:0,e:0,h:0,l:0,sp:0,pc:0};
+	let mut seen=0;
+	for field in text.split_whitespace(){
+		let mut parts=field.splitn(2,':');
+		let (name,value)=(parts.next()?,parts.next()?);
+		match name{
+			"SP" => state.sp=u16::from_str_radix(value,16).ok()?,
+			"PC" => state.pc=u16::from_str_radix(value,16).ok()?,
+			"A" | "F" | "B" | "C" | "D" | "E" | "H" | "L" => {
+				let value=u8::from_str_radix(value,16).ok()?;
+				match name{
+					"A" => state.a=value,
+					"F" => state.f=value,
+					"B" => state.b=value,
+					"C" => state.c=value,
+					"D" => state.d=value,
+					"E" => state.e=value,
+					"H" => state.h=value,
+					_   => state.l=value,
+				}
+			},
+			_ => continue,
+		}
+		seen+=1;
+	}
+	if seen==10 {Some(state)} else {None}
+}
+
+impl Display for CpuState{
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		write!(f,"A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X}",
+			self.a,self.f,self.b,self.c,self.d,self.e,self.h,self.l,self.sp,self.pc)
+	}
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{TraceComparer,Outcome,parse_line};
+	use std::io::Cursor;
+
+	#[test]
+	fn parse_doctor_line(){
+		let line="A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02";
+		let state=parse_line(line).unwrap();
+		assert_eq!(format!("{}",state),line[..line.find(" PCMEM").unwrap()]);
+	}
+
+	#[test]
+	fn reports_divergence(){
+		let log="A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100\n\
+		         A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0101\n";
+		let mut trace=TraceComparer::new(Box::new(Cursor::new(log)));
+		let mut state=parse_line(log.lines().next().unwrap()).unwrap();
+		assert!(matches!(trace.check(state),Outcome::Match));
+		state.a=0x02;
+		match trace.check(state){
+			Outcome::Diverged{line,expected,..} => {
+				assert_eq!(line,2);
+				assert_eq!(expected.pc,0x0101);
+			},
+			_ => panic!("expected a divergence"),
+		}
+		assert!(matches!(trace.check(state),Outcome::Ended(2)));
+	}
+}
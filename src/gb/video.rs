@@ -0,0 +1,159 @@
+//Typed views over VRAM and OAM so tools don't have to decode graphics
+//memory by hand.
+
+pub const SPRITE_COUNT: usize = 40;
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+//the PPU picks at most this many sprites a line, in OAM order.
+pub const SPRITES_PER_LINE: usize = 10;
+
+//8x8 tile, 2 bytes per row: low bits first, then high bits.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct TileData{
+	bytes: [u8;16],
+}
+
+impl TileData{
+	pub fn new(bytes: [u8;16])->Self{
+		TileData{bytes}
+	}
+
+	//tile `index` counting from 0x8000.
+	pub fn from_vram(vram: &[u8],index: usize)->Self{
+		let mut bytes=[0;16];
+		bytes.copy_from_slice(&vram[index*16..index*16+16]);
+		TileData{bytes}
+	}
+
+	//color number (0-3) before going through a palette.
+	pub fn pixel(&self,x: usize,y: usize)->u8{
+		let lo=self.bytes[y*2];
+		let hi=self.bytes[y*2+1];
+		let bit=7-x;
+		((hi>>bit)&1)<<1 | ((lo>>bit)&1)
+	}
+
+	pub fn bytes(&self)->&[u8;16]{
+		&self.bytes
+	}
+}
+
+//index into the tile data area for a tile number from a tile map. With
+//LCDC bit 4 off tile numbers are signed and relative to 0x9000.
+pub fn tile_index(tile: u8,unsigned_mode: bool)->usize{
+	if unsigned_mode {
+		tile as usize
+	}
+	else {
+		(256+(tile as i8) as isize) as usize
+	}
+}
+
+//one of the 40 OAM entries.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct SpriteEntry{
+	pub y: u8,
+	pub x: u8,
+	pub tile: u8,
+	pub flags: u8,
+}
+
+impl SpriteEntry{
+	pub fn from_oam(oam: &[u8],index: usize)->Self{
+		let entry=&oam[index*4..index*4+4];
+		SpriteEntry{y: entry[0],x: entry[1],tile: entry[2],flags: entry[3]}
+	}
+
+	//OAM coordinates are offset so sprites can be partially off screen.
+	pub fn screen_x(&self)->i16{
+		self.x as i16-8
+	}
+
+	pub fn screen_y(&self)->i16{
+		self.y as i16-16
+	}
+
+	pub fn behind_background(&self)->bool{
+		self.flags&0x80!=0
+	}
+
+	pub fn flip_y(&self)->bool{
+		self.flags&0x40!=0
+	}
+
+	pub fn flip_x(&self)->bool{
+		self.flags&0x20!=0
+	}
+
+	//0 for OBP0, 1 for OBP1.
+	pub fn palette(&self)->u8{
+		(self.flags>>4)&1
+	}
+
+	//`height` is 8 or 16 (LCDC bit 2).
+	pub fn on_line(&self,line: i16,height: i16)->bool{
+		line>=self.screen_y() && line<self.screen_y()+height
+	}
+}
+
+pub fn sprites(oam: &[u8])->Vec<SpriteEntry>{
+	(0..SPRITE_COUNT).map(|i| SpriteEntry::from_oam(oam,i)).collect()
+}
+
+//OAM indexes of the sprites on a line, split into the ones the PPU shows
+//and the ones over the limit. Sprites off screen horizontally still count.
+pub fn line_sprites(sprites: &[SpriteEntry],line: i16,height: i16)->(Vec<usize>,Vec<usize>){
+	let mut on_line: Vec<usize>=(0..sprites.len()).filter(|&i| sprites[i].on_line(line,height)).collect();
+	let dropped=on_line.split_off(on_line.len().min(SPRITES_PER_LINE));
+	(on_line,dropped)
+}
+
+#[cfg(test)]
+mod tests{
+
+	use super::{TileData,SpriteEntry,tile_index,line_sprites};
+
+	#[test]
+	fn tile_pixels(){
+		let mut bytes=[0;16];
+		bytes[0]=0b1010_0000;
+		bytes[1]=0b1100_0000;
+		let tile=TileData::new(bytes);
+		assert_eq!(tile.pixel(0,0),3);
+		assert_eq!(tile.pixel(1,0),2);
+		assert_eq!(tile.pixel(2,0),1);
+		assert_eq!(tile.pixel(3,0),0);
+	}
+
+	#[test]
+	fn signed_tile_index(){
+		assert_eq!(tile_index(0x80,true),0x80);
+		assert_eq!(tile_index(0x00,false),256);
+		assert_eq!(tile_index(0x80,false),128);
+	}
+
+	#[test]
+	fn sprite_entry(){
+		let oam=[16,8,0x42,0xB0];
+		let sprite=SpriteEntry::from_oam(&oam,0);
+		assert_eq!((sprite.screen_x(),sprite.screen_y()),(0,0));
+		assert!(sprite.behind_background() && sprite.flip_x() && !sprite.flip_y());
+		assert_eq!(sprite.palette(),1);
+	}
+
+	#[test]
+	fn ten_sprites_a_line(){
+		//12 sprites on lines 0-7, the last two x=0 so they're hidden anyway
+		let mut oam=vec![0;160];
+		for i in 0..12 {
+			oam[i*4]=16;
+			oam[i*4+1]=if i<10 {8+i as u8*8} else {0};
+		}
+		let sprites=super::sprites(&oam);
+		let (shown,dropped)=line_sprites(&sprites,7,8);
+		assert_eq!(shown,(0..10).collect::<Vec<_>>());
+		assert_eq!(dropped,vec![10,11]);
+		assert_eq!(line_sprites(&sprites,8,8),(vec![],vec![]));
+		assert_eq!(line_sprites(&sprites,15,16).1.len(),2);
+	}
+}
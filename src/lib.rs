@@ -0,0 +1,19 @@
+//! The emulator core, the binary in main.rs is one frontend over it. The
+//! `async` feature adds `gb::async_gameboy`, to run it inside tokio.
+//!
+//! ```
+//! use rust_gb_emu::gb::gameboy::GameBoy;
+//! use rust_gb_emu::gb::interconnect::RamInit;
+//! use rust_gb_emu::gb::testrom;
+//!
+//! let mut gb=GameBoy::new(testrom::tiny_rom(),RamInit::Zero);
+//! gb.set_verbose(false);
+//! gb.step_frame();
+//! assert_eq!(gb.frame(),1);
+//! ```
+extern crate toml;
+#[cfg(feature="async")]
+extern crate tokio;
+
+pub mod gb;
+
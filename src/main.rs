@@ -1,21 +1,734 @@
-#[macro_use]
-extern crate enum_primitive;
+extern crate rust_gb_emu;
 
-#[macro_use]
-extern crate num;
-
-mod gb;
+mod commands;
+mod compat_run;
+mod paced;
+mod sprite_step;
+mod rom_watch;
 
+use rust_gb_emu::gb;
 use gb::gameboy::*;
+use gb::frontend::{self,Frontend};
+use gb::cartridge::{self,Cartridge};
+use gb::save;
+use gb::trace::TraceComparer;
+use gb::interconnect::{RamInit,UnimplementedIo};
+use gb::printer::Printer;
+use gb::link::TcpLink;
+use gb::serial::SerialDevice;
+use gb::memory_map::{self,DumpFormat};
+use gb::sram_watch::SramWatch;
+use gb::regwatch::RegisterWatch;
+use gb::breakpoints::Breakpoint;
+use rom_watch::RomWatcher;
+use paced::Paced;
+use sprite_step::SpriteStep;
+use gb::rgbds_map::RgbdsMap;
+use gb::lockstep;
+use gb::isa;
+use gb::selftest::{self,Status};
 use std::env;
-use std::fs::File;
-use std::io::Read;
+use std::fs::{self,File};
+use std::io::{BufReader,BufWriter,Read};
+use std::path::{Path,PathBuf};
+use std::process;
+
+//the default command, running a rom. The others are in commands.rs.
+const RUN_USAGE: &str = "       rust-gb-emu [--frontend=console|headless] [--raw] [--compare-trace <log>]
+                   [--break-at-cycle <n>] [--break-at-frame <n>]
+                   [--break-at-pc <addr>] [--commands <addr> <actions>]
+                   [--ram-init <policy>] [--printer <dir>]
+                   [--link-listen <port>] [--link-connect <host:port>]
+                   [--coverage <file.cdl>] [--check-timing]
+                   [--cpu-speed <n>] [--dump-memory <start-end>]
+                   [--dump-format bytes|words|signed|bcd]
+                   [--watch-sram | --break-on-sram]
+                   [--frame-checksums <file>] [--watch-reg <reg[==value]>]
+                   [--watch] [--map <file.map>] [--diff-memory <start-end>]
+                   [--realtime] [--strict-header] [--history <n>]
+                   [--sprite-step <dir> [--tall-sprites]]
+                   [--unimplemented-io warn|panic] <rom>
+
+  --raw                  load a headerless binary at 0x150 in a 32KB cartridge
+  --compare-trace <log>  stop at the first instruction that differs from a
+                         reference log in gameboy-doctor format
+  --break-at-cycle <n>   stop once n cycles have run
+  --break-at-frame <n>   stop once n frames have run
+  --break-at-pc <addr>   stop before the instruction at addr (hex) runs
+  --commands <addr> <actions>
+                         run actions when pc reaches addr, separated by ';':
+                         print <reg>, regs, dumpmem <start:end> and
+                         continue, which keeps running instead of stopping.
+                         i.e. --commands 0150 \"print A; continue\"
+  --ram-init <policy>    wram/hram contents at power on: zero (default), ff,
+                         pattern, random or random:<seed>
+  --printer <dir>        plug a Game Boy Printer into the link port, prints
+                         are saved as PNGs in dir
+  --link-listen <port>   wait for another emulator to link up over TCP
+  --link-connect <addr>  link up with an emulator waiting at host:port
+  --coverage <file.cdl>  write a code/data log of the rom when the run ends,
+                         one byte per rom byte: bit 0 executed, bit 1 read
+  --check-timing         check the cycles of every instruction against the
+                         opcode table, printing a histogram when the run ends
+  --cpu-speed <n>        overclock the cpu n times (2, 4...) against the rest
+                         of the hardware, for games that lag
+  --dump-memory <range>  hexdump a range (hex, i.e. C000-C0FF) when the run
+                         ends. Mirrored bytes are marked with ~, open bus
+                         bytes print as --
+  --dump-format <fmt>    show --dump-memory as bytes (default), words (16 bit
+                         little endian), signed bytes or bcd
+  --watch-sram           print every write to cartridge ram with the pc and
+                         ram bank, to find a game's save routine
+  --break-on-sram        like --watch-sram but stop at the first write
+  --frame-checksums <f>  log a checksum of the registers and ram after every
+                         frame, diff the logs of two runs to find where they
+                         desync
+  --watch-reg <watch>    stop when a register (A..L, AF, BC, DE, HL, SP, PC)
+                         or flag (ZF, NF, HF, CF) changes, or with ==<hex>
+                         only when it changes to that value. Can be repeated
+  --watch                reload and reset whenever the rom file changes,
+                         keeping breakpoints and watches
+  --map <file.map>       RGBDS map file of the rom, used to say which
+                         section the cpu stopped in
+  --diff-memory <range>  print the bytes of a range (hex, i.e. C000-C0FF)
+                         that changed at the end of every frame. Can be
+                         repeated
+  --realtime             run at the speed of real hardware (59.73 frames
+                         per second) instead of as fast as possible
+  --strict-header        refuse roms whose file size, rom size byte and
+                         cartridge type disagree, instead of mirroring
+  --history <n>          print the registers before each of the last n
+                         instructions when the run ends, marking what each
+                         one changed
+  --sprite-step <dir>    after every frame save the sprites, tinted by OAM
+                         index, as PNGs in dir and print the ones dropped
+                         by the 10 sprites a line limit
+  --tall-sprites         sprites are 8x16 for --sprite-step
+  --unimplemented-io <p> what touching an IO register that isn't emulated
+                         does: warn (default) the first time for each
+                         register and read 0xFF, or panic
+
+Battery saves are kept next to the rom as <TITLE>-<checksum>.sav, so
+roms sharing a title don't share a save. Saves from older versions
+(<rom>.sav) are moved there the first time the rom runs.";
+
+fn usage()->String{
+	let synopses: Vec<String>=commands::COMMANDS.iter().map(commands::synopsis).collect();
+	format!("Usage: {}\n{}\n\nCommands:\n{}\n\nrust-gb-emu help <command> tells more about one.",
+		synopses.join("\n       "),RUN_USAGE,commands::summaries())
+}
+
+struct Options{
+	rom: String,
+	frontend: String,
+	raw: bool,
+	compare_trace: Option<String>,
+	break_at_cycle: Option<u64>,
+	break_at_frame: Option<u64>,
+	ram_init: RamInit,
+	serial: Option<Serial>,
+	coverage: Option<String>,
+	check_timing: bool,
+	cpu_speed: u32,
+	dump_memory: Option<(u16,u16)>,
+	dump_format: DumpFormat,
+	sram_watch: Option<SramWatch>,
+	frame_checksums: Option<String>,
+	register_watches: Vec<RegisterWatch>,
+	watch: bool,
+	map: Option<RgbdsMap>,
+	diff_memory: Vec<(u16,u16)>,
+	realtime: bool,
+	strict_header: bool,
+	history: Option<usize>,
+	breakpoints: Vec<Breakpoint>,
+	sprite_step: Option<String>,
+	tall_sprites: bool,
+	unimplemented_io: UnimplementedIo,
+}
+
+//what to plug into the link port.
+enum Serial{
+	Printer(String),
+	Listen(u16),
+	Connect(String),
+}
 
 fn main(){
-	let file_name=env::args().nth(1).unwrap();
-	let file_buf=load_file(file_name);
-	let mut gb=GameBoy::new(file_buf);
-	gb.run();
+	let args: Vec<String>=env::args().skip(1).collect();
+	match args.first().map(|a| &a[..]){
+		Some("export-save") => export_save(&args[1..]),
+		Some("import-save") => import_save(&args[1..]),
+		Some("compat-run")  => compat_run(&args[1..]),
+		Some("lockstep")    => lockstep(&args[1..]),
+		Some("selftest")    => self_test(),
+		Some("info")        => info(&args[1..]),
+		Some("logo-check")  => logo_check(&args[1..]),
+		Some("isa")         => isa(&args[1..]),
+		Some("help")        => help(&args[1..]),
+		_                   => run(parse_args(args)),
+	}
+}
+
+fn run(options: Options){
+	let mut frontend=frontend::from_name(&options.frontend).unwrap_or_else(||
+		fail(&format!("Unknown frontend {}",options.frontend))
+	);
+	let rom=load_rom(&options.rom,options.raw);
+	if options.strict_header {
+		check_header(&options.rom,&rom);
+	}
+	let mut gb=GameBoy::new(rom,options.ram_init);
+	gb.set_verbose(options.frontend!="headless");
+	let mut save_path=find_save(&options.rom,gb.interconnect().cartridge());
+	load_save(&mut gb,&save_path);
+	if let RamInit::Random(seed)=gb.interconnect().ram_init() {
+		println!("Ram randomized with seed {}, pass --ram-init random:{} to reproduce",seed,seed);
+	}
+	if let Some(log)=options.compare_trace {
+		let file=File::open(&log).unwrap_or_else(|e| fail(&format!("Cannot open {}: {}",log,e)));
+		gb.compare_trace(TraceComparer::new(Box::new(BufReader::new(file))));
+	}
+	if let Some(serial)=options.serial {
+		gb.connect_serial(serial_device(serial));
+	}
+	if options.coverage.is_some() {
+		gb.interconnect_mut().enable_coverage();
+	}
+	gb.set_cpu_speed(options.cpu_speed);
+	gb.interconnect_mut().set_unimplemented_io(options.unimplemented_io);
+	if let Some(path)=options.frame_checksums {
+		let file=File::create(&path).unwrap_or_else(|e| fail(&format!("Cannot create {}: {}",path,e)));
+		gb.log_checksums(Box::new(BufWriter::new(file)));
+	}
+	for &(start,end) in &options.diff_memory{
+		gb.diff_memory(start,end);
+	}
+	if let Some(steps)=options.history {
+		gb.record_history(steps);
+	}
+	for breakpoint in options.breakpoints{
+		gb.break_at_pc(breakpoint);
+	}
+	for watch in options.register_watches{
+		gb.watch_register(watch);
+	}
+	if let Some(watch)=options.sram_watch {
+		gb.watch_sram(watch);
+	}
+	if options.check_timing {
+		gb.check_timing();
+	}
+	if let Some(cycle)=options.break_at_cycle {
+		gb.break_at_cycle(cycle);
+	}
+	if let Some(frame)=options.break_at_frame {
+		gb.break_at_frame(frame);
+	}
+	let mut paced;
+	let frontend: &mut dyn Frontend=if options.realtime {
+		paced=Paced::new(&mut *frontend);
+		&mut paced
+	}
+	else {
+		&mut *frontend
+	};
+	let mut sprite_step;
+	let frontend: &mut dyn Frontend=match options.sprite_step{
+		Some(ref dir) => {
+			let height=if options.tall_sprites {16} else {8};
+			sprite_step=SpriteStep::new(frontend,PathBuf::from(dir),height);
+			&mut sprite_step
+		},
+		None          => frontend,
+	};
+	if options.watch {
+		let mut watcher=RomWatcher::new(frontend,PathBuf::from(&options.rom));
+		loop {
+			gb.run(&mut watcher);
+			if !watcher.changed() {
+				break;
+			}
+			watcher.rearm();
+			println!("{} changed, reloading",options.rom);
+			flush_save(&gb,&save_path);
+			gb.load_cartridge(load_rom(&options.rom,options.raw));
+			//the title or checksum may have changed with it
+			save_path=find_save(&options.rom,gb.interconnect().cartridge());
+			load_save(&mut gb,&save_path);
+		}
+	}
+	else {
+		gb.run(frontend);
+	}
+	if let (Some(path),Some(coverage))=(options.coverage,gb.interconnect().coverage()) {
+		let (executed,read)=coverage.totals();
+		println!("Coverage: {} bytes executed, {} bytes read",executed,read);
+		if let Err(e)=fs::write(&path,coverage.code_data_log()) {
+			eprintln!("Cannot write {}: {}",path,e);
+		}
+	}
+	if let Some(ref map)=options.map {
+		let pc=gb.cpu().state().pc;
+		let bank=gb.interconnect().cartridge().current_rom_bank();
+		match map.section_at(pc,bank){
+			Some(section) => println!("PC {:04X} is in section \"{}\" (bank {})",pc,section.name,section.bank),
+			None          => println!("PC {:04X} is outside every rom section in the map",pc),
+		}
+	}
+	if let Some((start,end))=options.dump_memory {
+		let inter=gb.interconnect();
+		let peek=|a| if inter.is_mapped(a) {Some(inter.peek(a))} else {None};
+		for line in memory_map::format_memory_dump_as(start,end,options.dump_format,&peek){
+			println!("{}",line);
+		}
+	}
+	if let Some(history)=gb.history() {
+		println!("Registers before the last {} instructions:",history.entries().count());
+		for line in history.format(){
+			println!("{}",line);
+		}
+	}
+	if let Some(timing)=gb.timing() {
+		println!("Instruction timing, {} mismatches:",timing.mismatches());
+		for line in timing.report(){
+			println!("{}",line);
+		}
+	}
+	flush_save(&gb,&save_path);
+}
+
+fn load_rom(rom: &str,raw: bool)->Vec<u8>{
+	let file_buf=load_file(rom.to_string());
+	if !raw {
+		return file_buf;
+	}
+	cartridge::raw_image(&file_buf).unwrap_or_else(|e| fail(&e))
+}
+
+//where the rom's save goes, moving it there from the old <rom>.sav.
+fn find_save(rom: &str,cart: &Cartridge)->PathBuf{
+	match save::migrate(Path::new(rom),cart){
+		Ok(Some(legacy)) => println!("Moved {} to {}",legacy.display(),save::save_path(Path::new(rom),cart).display()),
+		Ok(None)         => {},
+		Err(e)           => eprintln!("Cannot move the old save for {}: {}",rom,e),
+	}
+	save::save_path(Path::new(rom),cart)
+}
+
+//--strict-header, exits listing every disagreement.
+fn check_header(path: &str,rom: &[u8]){
+	let problems=cartridge::header_problems(rom);
+	if problems.is_empty() {
+		return;
+	}
+	for problem in &problems{
+		eprintln!("{}: {}",path,problem);
+	}
+	eprintln!("Not loading {}, its header has {} problem(s) (--strict-header)",path,problems.len());
+	process::exit(1);
+}
+
+fn load_save(gb: &mut GameBoy,save_path: &Path){
+	if !gb.interconnect().cartridge().has_battery() {
+		return;
+	}
+	if let Ok(data)=fs::read(save_path) {
+		gb.interconnect_mut().cartridge_mut().load_ram(&data);
+	}
+}
+
+fn flush_save(gb: &GameBoy,save_path: &Path){
+	if !gb.interconnect().cartridge().has_battery() {
+		return;
+	}
+	match fs::write(save_path,gb.interconnect().cartridge().ram()){
+		Ok(())  => println!("Save ram flushed to {}",save_path.display()),
+		Err(e)  => eprintln!("Cannot write {}: {}",save_path.display(),e),
+	}
+}
+
+fn compat_run(args: &[String]){
+	let mut dir=None;
+	let mut frames=60;
+	let mut report=None;
+	let mut args=args.iter().cloned();
+	while let Some(arg)=args.next(){
+		match &arg[..]{
+			"--frames" => frames=number_arg(&arg,args.next()),
+			"--report" => report=Some(args.next().unwrap_or_else(|| fail("--report needs a file"))),
+			_          => dir=Some(arg),
+		}
+	}
+	let dir=dir.unwrap_or_else(|| fail("compat-run needs a directory"));
+	let lines=compat_run::run(Path::new(&dir),frames).join("\n");
+	match report{
+		Some(path) => fs::write(&path,lines+"\n").unwrap_or_else(|e| fail(&format!("Cannot write {}: {}",path,e))),
+		None       => println!("{}",lines),
+	}
+}
+
+fn lockstep(args: &[String]){
+	let mut rom=None;
+	let mut frames=600;
+	let mut ram_init=RamInit::Zero;
+	let mut args=args.iter().cloned();
+	while let Some(arg)=args.next(){
+		match &arg[..]{
+			"--frames"   => frames=number_arg(&arg,args.next()),
+			"--ram-init" => ram_init=args.next().as_ref().and_then(|p| RamInit::from_name(p))
+				.unwrap_or_else(|| fail("--ram-init needs zero, ff, pattern, random or random:<seed>")),
+			_            => rom=Some(arg),
+		}
+	}
+	let rom=load_file(rom.unwrap_or_else(|| fail("lockstep needs <rom>")));
+	let mut plain=GameBoy::new(rom.clone(),ram_init);
+	let mut instrumented=GameBoy::new(rom,ram_init);
+	for gb in [&mut plain,&mut instrumented]{
+		gb.set_verbose(false);
+	}
+	instrumented.check_timing();
+	match lockstep::run(&mut plain,&mut instrumented,frames){
+		Ok(())           => println!("Both instances matched for {} frames",frames),
+		Err(divergence)  => {
+			eprintln!("{}",divergence);
+			process::exit(1);
+		},
+	}
+}
+
+fn info(args: &[String]){
+	let rom=match args{
+		[rom] => rom,
+		_     => fail("info needs <rom>"),
+	};
+	let cart=Cartridge::new(load_file(rom.clone()));
+	let check=|ok| if ok {"ok"} else {"MISMATCH"};
+	println!("Title:           {}",cart.title());
+	println!("Cartridge type:  0x{:02x}{}",cart.cart_type(),if cart.has_battery() {" (battery)"} else {""});
+	println!("Rom:             {} banks, {} bytes in the file",cart.rom_bank_count(),cart.rom().len());
+	println!("Ram:             {} bytes",cart.ram().len());
+	println!("Header checksum: 0x{:02x} {}",cart.header_checksum(),check(cart.header_checksum_ok()));
+	println!("Global checksum: 0x{:04x} {}",cart.global_checksum(),check(cart.global_checksum_ok()));
+	for problem in cartridge::header_problems(cart.rom()){
+		println!("Header problem:  {}",problem);
+	}
+}
+
+fn logo_check(args: &[String]){
+	let rom=match args{
+		[rom] => load_file(rom.clone()),
+		_     => fail("logo-check needs <rom>"),
+	};
+	let logo=rom.get(0x104..0x134).unwrap_or(&[]);
+	for line in cartridge::logo_bitmap(logo){
+		println!("{}",line.trim_end());
+	}
+	let mismatches=cartridge::logo_mismatches(&rom);
+	if mismatches.is_empty() {
+		println!("Logo ok");
+		return;
+	}
+	println!("Logo MISMATCH at {} bytes, a DMG won't boot this rom. Expected:",mismatches.len());
+	for line in cartridge::logo_bitmap(&cartridge::NINTENDO_LOGO){
+		println!("{}",line.trim_end());
+	}
+	for address in mismatches{
+		let found=rom.get(address as usize).map_or(String::from("--"),|b| format!("{:02X}",b));
+		println!("{:04X}: {} instead of {:02X}",address,found,cartridge::NINTENDO_LOGO[address as usize-0x104]);
+	}
+	process::exit(1);
+}
+
+fn isa(args: &[String]){
+	let json=match args{
+		[dump] if dump=="dump"                        => false,
+		[dump,json] if dump=="dump" && json=="--json" => true,
+		_                                             => fail("isa needs dump [--json]"),
+	};
+	let instructions=isa::all();
+	if json {
+		print!("{}",isa::to_json(&instructions));
+		return;
+	}
+	for i in instructions{
+		let opcode=if i.prefixed {format!("CB {:02X}",i.opcode)} else {format!("{:02X}",i.opcode)};
+		let cycles=match i.cycles_taken{
+			Some(taken) => format!("{}/{}",i.cycles,taken),
+			None        => i.cycles.to_string(),
+		};
+		println!("{:<6}{:<16}{:>2} {:>6} {}",opcode,i.text(),i.length,cycles,i.flags);
+	}
+}
+
+fn help(args: &[String]){
+	match args{
+		[]     => println!("{}",usage()),
+		[name] => match commands::find(name){
+			Some(command) => print!("{}",commands::help(command)),
+			None          => fail(&format!("No command called {}",name)),
+		},
+		_      => fail("help takes at most one command"),
+	}
+}
+
+fn self_test(){
+	let mut failed=0;
+	for check in selftest::run(){
+		let (status,detail)=match check.status{
+			Status::Pass(detail) => ("PASS",detail),
+			Status::Fail(detail) => {
+				failed+=1;
+				("FAIL",detail)
+			},
+			Status::Skip(detail) => ("SKIP",detail),
+		};
+		println!("{} {:<18} {}",status,check.name,detail);
+	}
+	if failed>0 {
+		println!("{} checks failed",failed);
+		process::exit(1);
+	}
+}
+
+//copies the save for a rom out to another emulator.
+fn export_save(args: &[String]){
+	let (rom,out)=match args{
+		[rom,out] => (rom,out),
+		_         => fail("export-save needs <rom> <out.sav>"),
+	};
+	let cart=Cartridge::new(load_file(rom.clone()));
+	let save_path=find_save(rom,&cart);
+	let data=fs::read(&save_path).unwrap_or_else(|e|
+		fail(&format!("No save at {}: {}",save_path.display(),e))
+	);
+	let ram=save::fit(&data,cart.ram().len());
+	fs::write(out,&ram).unwrap_or_else(|e| fail(&format!("Cannot write {}: {}",out,e)));
+	println!("Exported {} bytes to {}",ram.len(),out);
+}
+
+//replaces the save for a rom with one from another emulator.
+fn import_save(args: &[String]){
+	let (rom,input)=match args{
+		[rom,input] => (rom,input),
+		_           => fail("import-save needs <rom> <in.sav>"),
+	};
+	let cart=Cartridge::new(load_file(rom.clone()));
+	if !cart.has_battery() {
+		fail(&format!("{} has no battery backed ram",rom));
+	}
+	let data=fs::read(input).unwrap_or_else(|e| fail(&format!("Cannot read {}: {}",input,e)));
+	let size=cart.ram().len();
+	if data.len()!=size {
+		eprintln!("Warning: {} is {} bytes, the cartridge has {}, resizing",input,data.len(),size);
+	}
+	let save_path=find_save(rom,&cart);
+	fs::write(&save_path,save::fit(&data,size)).unwrap_or_else(|e|
+		fail(&format!("Cannot write {}: {}",save_path.display(),e))
+	);
+	println!("Imported {} into {}",input,save_path.display());
+}
+
+
+fn parse_args(args: Vec<String>)->Options{
+	let mut rom=None;
+	let mut frontend=String::from("console");
+	let mut raw=false;
+	let mut compare_trace=None;
+	let mut break_at_cycle=None;
+	let mut break_at_frame=None;
+	let mut ram_init=RamInit::Zero;
+	let mut serial=None;
+	let mut coverage=None;
+	let mut check_timing=false;
+	let mut cpu_speed=1;
+	let mut dump_memory=None;
+	let mut dump_format=DumpFormat::Bytes;
+	let mut sram_watch=None;
+	let mut frame_checksums=None;
+	let mut register_watches=Vec::new();
+	let mut watch=false;
+	let mut map=None;
+	let mut diff_memory=Vec::new();
+	let mut realtime=false;
+	let mut strict_header=false;
+	let mut history=None;
+	let mut breakpoints=Vec::new();
+	let mut sprite_step=None;
+	let mut tall_sprites=false;
+	let mut unimplemented_io=UnimplementedIo::Warn;
+	let mut args=args.into_iter();
+	while let Some(arg)=args.next(){
+		if let Some(name)=arg.strip_prefix("--frontend="){
+			frontend=name.to_string();
+		}
+		else if arg=="--frontend"{
+			frontend=args.next().unwrap_or_else(|| fail("--frontend needs a value"));
+		}
+		else if arg=="--raw"{
+			raw=true;
+		}
+		else if arg=="--compare-trace"{
+			compare_trace=Some(args.next().unwrap_or_else(|| fail("--compare-trace needs a log")));
+		}
+		else if arg=="--break-at-cycle"{
+			break_at_cycle=Some(number_arg(&arg,args.next()));
+		}
+		else if arg=="--break-at-frame"{
+			break_at_frame=Some(number_arg(&arg,args.next()));
+		}
+		else if arg=="--ram-init"{
+			ram_init=args.next().as_ref().and_then(|p| RamInit::from_name(p))
+				.unwrap_or_else(|| fail("--ram-init needs zero, ff, pattern, random or random:<seed>"));
+		}
+		else if arg=="--printer"{
+			serial=Some(Serial::Printer(args.next().unwrap_or_else(|| fail("--printer needs a directory"))));
+		}
+		else if arg=="--link-listen"{
+			let port=args.next().and_then(|p| p.parse().ok());
+			serial=Some(Serial::Listen(port.unwrap_or_else(|| fail("--link-listen needs a port"))));
+		}
+		else if arg=="--link-connect"{
+			serial=Some(Serial::Connect(args.next().unwrap_or_else(|| fail("--link-connect needs host:port"))));
+		}
+		else if arg=="--coverage"{
+			coverage=Some(args.next().unwrap_or_else(|| fail("--coverage needs a file")));
+		}
+		else if arg=="--check-timing"{
+			check_timing=true;
+		}
+		else if arg=="--cpu-speed"{
+			cpu_speed=number_arg(&arg,args.next()) as u32;
+			if cpu_speed==0 {
+				fail("--cpu-speed needs a number above 0");
+			}
+		}
+		else if arg=="--dump-memory"{
+			dump_memory=Some(args.next().as_ref().and_then(|r| address_range(r))
+				.unwrap_or_else(|| fail("--dump-memory needs a hex range like C000-C0FF")));
+		}
+		else if arg=="--dump-format"{
+			dump_format=args.next().as_ref().and_then(|f| DumpFormat::from_name(f))
+				.unwrap_or_else(|| fail("--dump-format needs bytes, words, signed or bcd"));
+		}
+		else if arg=="--watch-sram"{
+			sram_watch=Some(SramWatch::Log);
+		}
+		else if arg=="--break-on-sram"{
+			sram_watch=Some(SramWatch::Break);
+		}
+		else if arg=="--frame-checksums"{
+			frame_checksums=Some(args.next().unwrap_or_else(|| fail("--frame-checksums needs a file")));
+		}
+		else if arg=="--watch-reg"{
+			let watch=args.next().as_ref().and_then(|w| RegisterWatch::parse(w));
+			register_watches.push(watch.unwrap_or_else(|| fail("--watch-reg needs a register like HL or A==FF")));
+		}
+		else if arg=="--watch"{
+			watch=true;
+		}
+		else if arg=="--map"{
+			let path=args.next().unwrap_or_else(|| fail("--map needs a file"));
+			let text=fs::read_to_string(&path).unwrap_or_else(|e| fail(&format!("Cannot read {}: {}",path,e)));
+			map=Some(RgbdsMap::parse(&text));
+		}
+		else if arg=="--realtime"{
+			realtime=true;
+		}
+		else if arg=="--strict-header"{
+			strict_header=true;
+		}
+		else if arg=="--diff-memory"{
+			diff_memory.push(args.next().as_ref().and_then(|r| address_range(r))
+				.unwrap_or_else(|| fail("--diff-memory needs a hex range like C000-C0FF")));
+		}
+		else if arg=="--break-at-pc"{
+			let pc=args.next().as_ref().and_then(|a| u16::from_str_radix(a.trim_start_matches("0x"),16).ok())
+				.unwrap_or_else(|| fail("--break-at-pc needs a hex address"));
+			breakpoints.push(Breakpoint::new(pc));
+		}
+		else if arg=="--commands"{
+			let pc=args.next().as_ref().and_then(|a| u16::from_str_radix(a.trim_start_matches("0x"),16).ok())
+				.unwrap_or_else(|| fail("--commands needs a hex address and a list of actions"));
+			let script=args.next().unwrap_or_else(|| fail("--commands needs a list of actions"));
+			breakpoints.push(Breakpoint::with_actions(pc,&script)
+				.unwrap_or_else(|e| fail(&format!("--commands: {}",e))));
+		}
+		else if arg=="--sprite-step"{
+			sprite_step=Some(args.next().unwrap_or_else(|| fail("--sprite-step needs a directory")));
+		}
+		else if arg=="--tall-sprites"{
+			tall_sprites=true;
+		}
+		else if arg=="--unimplemented-io"{
+			unimplemented_io=args.next().as_ref().and_then(|p| UnimplementedIo::from_name(p))
+				.unwrap_or_else(|| fail("--unimplemented-io needs warn or panic"));
+		}
+		else if arg=="--history"{
+			history=Some(number_arg(&arg,args.next()) as usize);
+		}
+		else if arg.starts_with("--"){
+			fail(&format!("Unknown option {}",arg));
+		}
+		else{
+			rom=Some(arg);
+		}
+	}
+	Options{
+		rom: rom.unwrap_or_else(|| fail("Missing rom")),
+		frontend,
+		raw,
+		compare_trace,
+		break_at_cycle,
+		break_at_frame,
+		ram_init,
+		serial,
+		coverage,
+		check_timing,
+		cpu_speed,
+		dump_memory,
+		dump_format,
+		sram_watch,
+		frame_checksums,
+		register_watches,
+		watch,
+		map,
+		diff_memory,
+		realtime,
+		strict_header,
+		history,
+		breakpoints,
+		sprite_step,
+		tall_sprites,
+		unimplemented_io,
+	}
+}
+
+fn serial_device(serial: Serial)->Box<dyn SerialDevice>{
+	let link=match serial{
+		Serial::Printer(dir)     => return Box::new(Printer::new(PathBuf::from(dir))),
+		Serial::Listen(port)     => TcpLink::listen(port),
+		Serial::Connect(address) => TcpLink::connect(&address[..]),
+	};
+	Box::new(link.unwrap_or_else(|e| fail(&format!("Cannot link: {}",e))))
+}
+
+fn number_arg(option: &str,value: Option<String>)->u64{
+	value.and_then(|v| v.parse().ok())
+		.unwrap_or_else(|| fail(&format!("{} needs a number",option)))
+}
+
+//"C000-C0FF", inclusive.
+fn address_range(range: &str)->Option<(u16,u16)>{
+	let mut parts=range.splitn(2,'-');
+	let start=u16::from_str_radix(parts.next()?,16).ok()?;
+	let end=u16::from_str_radix(parts.next()?,16).ok()?;
+	if start<=end {Some((start,end))} else {None}
+}
+
+fn fail(message: &str)->!{
+	eprintln!("{}\n{}",message,usage());
+	process::exit(1);
 }
 
 fn load_file(file_name: String) -> Vec<u8>{
@@ -23,4 +736,4 @@ fn load_file(file_name: String) -> Vec<u8>{
 	let mut file_buf = Vec::new();
 	file.read_to_end(&mut file_buf).unwrap();
 	file_buf
-} 
\ No newline at end of file
+}
@@ -0,0 +1,31 @@
+//--realtime: wraps the real frontend and holds every new frame back until
+//real hardware would have got to it.
+use rust_gb_emu::gb::frontend::{Frontend,Wrapper};
+use rust_gb_emu::gb::gameboy::GameBoy;
+use rust_gb_emu::gb::pacer::{FramePacer,FRAME_RATE};
+
+pub struct Paced<'a>{
+	inner: &'a mut dyn Frontend,
+	pacer: FramePacer,
+}
+
+impl<'a> Paced<'a>{
+	pub fn new(inner: &'a mut dyn Frontend)->Self{
+		Paced{inner,pacer: FramePacer::new(FRAME_RATE)}
+	}
+}
+
+impl<'a> Wrapper for Paced<'a>{
+	fn inner(&self)->&dyn Frontend{
+		self.inner
+	}
+
+	fn inner_mut(&mut self)->&mut dyn Frontend{
+		self.inner
+	}
+
+	fn present_frame(&mut self,gb: &GameBoy){
+		self.pacer.wait();
+		self.inner.present_frame(gb);
+	}
+}
@@ -0,0 +1,65 @@
+//--watch: wraps the real frontend and ends the run as soon as the rom file
+//changes on disk, so it can be reloaded.
+use rust_gb_emu::gb::frontend::{Frontend,Wrapper};
+use rust_gb_emu::gb::joypad::JoypadState;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration,Instant,SystemTime};
+
+//no need to stat the file every frame.
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct RomWatcher<'a>{
+	inner: &'a mut dyn Frontend,
+	path: PathBuf,
+	modified: Option<SystemTime>,
+	last_check: Instant,
+	changed: bool,
+}
+
+impl<'a> RomWatcher<'a>{
+	pub fn new(inner: &'a mut dyn Frontend,path: PathBuf)->Self{
+		let modified=modified(&path);
+		RomWatcher{inner,path,modified,last_check: Instant::now(),changed: false}
+	}
+
+	//whether the run ended because the rom changed.
+	pub fn changed(&self)->bool{
+		self.changed
+	}
+
+	//call after reloading, to wait for the next change.
+	pub fn rearm(&mut self){
+		self.modified=modified(&self.path);
+		self.changed=false;
+	}
+}
+
+fn modified(path: &PathBuf)->Option<SystemTime>{
+	fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl<'a> Wrapper for RomWatcher<'a>{
+	fn inner(&self)->&dyn Frontend{
+		self.inner
+	}
+
+	fn inner_mut(&mut self)->&mut dyn Frontend{
+		self.inner
+	}
+
+	fn poll_input(&mut self)->JoypadState{
+		let keys=self.inner.poll_input();
+		if self.last_check.elapsed()>=CHECK_INTERVAL {
+			self.last_check=Instant::now();
+			//a file being rewritten can briefly be missing, wait for it
+			let now=modified(&self.path);
+			self.changed=now.is_some() && now!=self.modified;
+		}
+		keys
+	}
+
+	fn should_quit(&self)->bool{
+		self.changed || self.inner.should_quit()
+	}
+}
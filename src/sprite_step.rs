@@ -0,0 +1,52 @@
+//--sprite-step: wraps the real frontend and, after every frame, saves the
+//sprites tinted by OAM index as <dir>/sprites_<frame>.png and prints the
+//ones dropped by the 10 a line limit.
+use rust_gb_emu::gb::frontend::{Frontend,Wrapper};
+use rust_gb_emu::gb::gameboy::GameBoy;
+use rust_gb_emu::gb::sprite_debug;
+use rust_gb_emu::gb::video::{SCREEN_WIDTH,SCREEN_HEIGHT};
+use rust_gb_emu::gb::png;
+use std::fs;
+use std::path::PathBuf;
+
+pub struct SpriteStep<'a>{
+	inner: &'a mut dyn Frontend,
+	output_dir: PathBuf,
+	//8 or 16, LCDC isn't emulated yet to tell.
+	height: i16,
+}
+
+impl<'a> SpriteStep<'a>{
+	pub fn new(inner: &'a mut dyn Frontend,output_dir: PathBuf,height: i16)->Self{
+		SpriteStep{inner,output_dir,height}
+	}
+
+	fn save_sprites(&self,gb: &GameBoy){
+		let frame=gb.frame();
+		let inter=gb.interconnect();
+		for line in sprite_debug::dropped_sprites(inter.oam(),self.height){
+			println!("frame {}: {} dropped",frame,line);
+		}
+		let pixels=sprite_debug::composite(inter.oam(),inter.vram(),self.height);
+		let png=png::encode_rgb(SCREEN_WIDTH as u32,SCREEN_HEIGHT as u32,&pixels);
+		let path=self.output_dir.join(format!("sprites_{:05}.png",frame));
+		if let Err(e)=fs::write(&path,png) {
+			eprintln!("Cannot save {}: {}",path.display(),e);
+		}
+	}
+}
+
+impl<'a> Wrapper for SpriteStep<'a>{
+	fn inner(&self)->&dyn Frontend{
+		self.inner
+	}
+
+	fn inner_mut(&mut self)->&mut dyn Frontend{
+		self.inner
+	}
+
+	fn present_frame(&mut self,gb: &GameBoy){
+		self.save_sprites(gb);
+		self.inner.present_frame(gb);
+	}
+}